@@ -0,0 +1,132 @@
+/*
+ * srx: The fast Symbol Ranking based compressor.
+ * Copyright (C) 2023-2024  Mai Thanh Minh (a.k.a. thanhminhmr)
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU General Public License as published by the Free Software
+ * Foundation, either  version 3 of the  License,  or (at your option) any later
+ * version.
+ *
+ * This program  is distributed in the hope  that it will be useful, but WITHOUT
+ * ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+ * FOR  A PARTICULAR PURPOSE. See  the  GNU  General  Public   License  for more
+ * details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+ */
+
+// Time srx::verify against decompressing the same file, to a file as a full decompression does and
+// to nowhere, for a single stream and for a blocked file, whose blocks are checked on every core.
+// Run with: cargo bench --bench verify
+
+use srx::{compress, decompress, verify, AnyResult, Checksum, Config};
+use std::env::temp_dir;
+use std::fs::{remove_file, File};
+use std::io::{sink, BufWriter, Cursor, Sink};
+use std::path::PathBuf;
+use std::thread::available_parallelism;
+use std::time::{Duration, Instant};
+
+// -----------------------------------------------
+
+const DATA_SIZE: usize = 64 << 20;
+const RUNS: usize = 5;
+
+// -----------------------------------------------
+
+// words picked by a small generator, compressible about as much as text is
+fn sample_data() -> Vec<u8> {
+	let words: Vec<&[u8]> =
+		b"the of and to in is that for it as was with be by on not he this are or"
+			.split(|byte| *byte == b' ')
+			.collect();
+	let mut state: u64 = 0x2545F4914F6CDD1D;
+	let mut data: Vec<u8> = Vec::with_capacity(DATA_SIZE + 16);
+	while data.len() < DATA_SIZE {
+		state = state
+			.wrapping_mul(6364136223846793005)
+			.wrapping_add(1442695040888963407);
+		data.extend_from_slice(words[(state >> 33) as usize % words.len()]);
+		data.push(if (state >> 20).is_multiple_of(13) {
+			b'\n'
+		} else {
+			b' '
+		});
+	}
+	data.truncate(DATA_SIZE);
+	data
+}
+
+// the fastest of a few runs
+fn best<F: FnMut() -> AnyResult<()>>(mut run: F) -> AnyResult<Duration> {
+	let mut best: Duration = Duration::MAX;
+	for _ in 0..RUNS {
+		let start: Instant = Instant::now();
+		run()?;
+		best = best.min(start.elapsed());
+	}
+	Ok(best)
+}
+
+fn report(name: &str, time: Duration, baseline: Duration) {
+	println!(
+		"  {:<24} {:>8.3} s {:>8.2} MiB/s {:>7.1}%",
+		name,
+		time.as_secs_f64(),
+		DATA_SIZE as f64 / time.as_secs_f64() / (1 << 20) as f64,
+		time.as_secs_f64() / baseline.as_secs_f64() * 100.0
+	);
+}
+
+fn main() -> AnyResult<()> {
+	let data: Vec<u8> = sample_data();
+	let path: PathBuf = temp_dir().join(format!("srx-bench-verify-{}", std::process::id()));
+	let cores: usize = available_parallelism().map_or(1, |cores| cores.get());
+	for (checksum, block_size) in [
+		(Checksum::None, None),
+		(Checksum::Blake3, None),
+		(Checksum::Blake3, Some(4 << 20)),
+	] {
+		let config: Config = Config {
+			checksum,
+			block_size,
+			..Config::default()
+		};
+		let (_, compressed): (Cursor<&[u8]>, Vec<u8>) =
+			compress(Cursor::new(data.as_slice()), Vec::new(), &config)?;
+		println!(
+			"{} MiB, checksum {}, {}, {} bytes compressed, {} cores",
+			DATA_SIZE >> 20,
+			checksum.name(),
+			block_size.map_or("one stream", |_| "blocks of 4 MiB"),
+			compressed.len(),
+			cores
+		);
+		let to_file: Duration = best(|| {
+			let output: BufWriter<File> = BufWriter::new(File::create(&path)?);
+			let (_, output): (Cursor<&[u8]>, BufWriter<File>) =
+				decompress(Cursor::new(compressed.as_slice()), output)?;
+			output
+				.into_inner()
+				.map_err(|error| error.into_error())?
+				.sync_all()?;
+			Ok(())
+		})?;
+		let to_sink: Duration = best(|| {
+			let (_, _): (Cursor<&[u8]>, Sink) =
+				decompress(Cursor::new(compressed.as_slice()), sink())?;
+			Ok(())
+		})?;
+		let verified: Duration = best(|| {
+			verify(Cursor::new(compressed.as_slice()))?;
+			Ok(())
+		})?;
+		report("decompress to a file", to_file, to_file);
+		report("decompress to nowhere", to_sink, to_file);
+		report("verify", verified, to_file);
+	}
+	remove_file(&path)?;
+	Ok(())
+}
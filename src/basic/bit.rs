@@ -17,6 +17,7 @@
  *
  */
 
+/// A single coded bit.
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
 #[repr(usize)]
 pub enum Bit {
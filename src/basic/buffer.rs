@@ -17,21 +17,30 @@
  *
  */
 
+use super::pool::{acquire, release};
+use std::mem::take;
 use std::ops::{Deref, DerefMut};
 
 // -----------------------------------------------
 
-// A constant size array in a Box
+// A fixed size array in a Box, recycled through the buffer pool when it is enabled
 #[derive(Clone)]
-pub struct Buffer<T: Copy, const SIZE: usize>(Box<[T]>);
+pub struct Buffer<T: Copy + Send + 'static>(Box<[T]>);
 
-impl<T: Copy + Default, const SIZE: usize> Buffer<T, SIZE> {
-	pub fn new() -> Self {
-		Self(vec![Default::default(); SIZE].into_boxed_slice())
+impl<T: Copy + Default + Send + 'static> Buffer<T> {
+	pub fn new(size: usize) -> Self {
+		match acquire::<T>(size) {
+			Some(mut slice) => {
+				// a recycled slice still contains old data, reset it
+				slice.fill(Default::default());
+				Self(slice)
+			}
+			None => Self(vec![Default::default(); size].into_boxed_slice()),
+		}
 	}
 }
 
-impl<T: Copy, const SIZE: usize> Deref for Buffer<T, SIZE> {
+impl<T: Copy + Send + 'static> Deref for Buffer<T> {
 	type Target = [T];
 
 	fn deref(&self) -> &Self::Target {
@@ -39,8 +48,14 @@ impl<T: Copy, const SIZE: usize> Deref for Buffer<T, SIZE> {
 	}
 }
 
-impl<T: Copy + Send + 'static, const SIZE: usize> DerefMut for Buffer<T, SIZE> {
+impl<T: Copy + Send + 'static> DerefMut for Buffer<T> {
 	fn deref_mut(&mut self) -> &mut Self::Target {
 		self.0.deref_mut()
 	}
 }
+
+impl<T: Copy + Send + 'static> Drop for Buffer<T> {
+	fn drop(&mut self) {
+		release(take(&mut self.0));
+	}
+}
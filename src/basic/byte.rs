@@ -17,7 +17,7 @@
  *
  */
 
-// An u8 in usize footprint
+/// An u8 in usize footprint.
 #[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
 pub struct Byte(usize);
 
@@ -0,0 +1,85 @@
+/*
+ * srx: The fast Symbol Ranking based compressor.
+ * Copyright (C) 2023-2024  Mai Thanh Minh (a.k.a. thanhminhmr)
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU General Public License as published by the Free Software
+ * Foundation, either  version 3 of the  License,  or (at your option) any later
+ * version.
+ *
+ * This program  is distributed in the hope  that it will be useful, but WITHOUT
+ * ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+ * FOR  A PARTICULAR PURPOSE. See  the  GNU  General  Public   License  for more
+ * details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+ */
+
+// -----------------------------------------------
+
+// Instrumentation with the tracing crate, when built with the tracing feature. Without it a span is
+// an empty struct and an event is nothing, so the pipeline pays for none of it. Spans are made in
+// the thread that starts a pipeline, so that the spans of its threads are children of the current
+// one, and entered by the thread they are for. A span also keeps the dispatcher of the thread that
+// made it, which becomes the default of the thread that enters it, so that the events of the
+// pipeline threads reach the subscriber of the caller and not only the global one.
+
+#[cfg(feature = "tracing")]
+pub struct PipelineSpan {
+	pub span: tracing::Span,
+	pub dispatch: tracing::Dispatch,
+}
+
+#[cfg(not(feature = "tracing"))]
+pub struct PipelineSpan;
+
+#[cfg(feature = "tracing")]
+macro_rules! pipeline_span {
+	($($arg:tt)*) => {
+		$crate::basic::PipelineSpan {
+			span: tracing::info_span!($($arg)*),
+			dispatch: tracing::dispatcher::get_default(|dispatch| dispatch.clone()),
+		}
+	};
+}
+
+#[cfg(not(feature = "tracing"))]
+macro_rules! pipeline_span {
+	($($arg:tt)*) => {
+		$crate::basic::PipelineSpan
+	};
+}
+
+// enter the span until the end of the block
+#[cfg(feature = "tracing")]
+macro_rules! enter_span {
+	($span:expr) => {
+		let span: $crate::basic::PipelineSpan = $span;
+		let _dispatch: tracing::dispatcher::DefaultGuard =
+			tracing::dispatcher::set_default(&span.dispatch);
+		let _entered: tracing::span::EnteredSpan = span.span.entered();
+	};
+}
+
+#[cfg(not(feature = "tracing"))]
+macro_rules! enter_span {
+	($span:expr) => {
+		let _entered: $crate::basic::PipelineSpan = $span;
+	};
+}
+
+#[cfg(feature = "tracing")]
+macro_rules! pipeline_event {
+	($($arg:tt)*) => {
+		tracing::trace!($($arg)*)
+	};
+}
+
+#[cfg(not(feature = "tracing"))]
+macro_rules! pipeline_event {
+	($($arg:tt)*) => {};
+}
+
+pub(crate) use {enter_span, pipeline_event, pipeline_span};
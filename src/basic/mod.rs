@@ -21,12 +21,22 @@ mod bit;
 mod buffer;
 mod byte;
 mod error;
+mod instrument;
 mod io;
 mod pipe;
+mod pool;
+mod tuning;
+
+#[cfg(test)]
+mod test;
 
 pub use self::bit::Bit;
 pub use self::buffer::Buffer;
 pub use self::byte::Byte;
 pub use self::error::{AnyError, AnyResult};
+pub use self::instrument::PipelineSpan;
+pub(crate) use self::instrument::{enter_span, pipeline_event, pipeline_span};
 pub use self::io::Closable;
 pub use self::pipe::{pipe, BufferedInputPipe, BufferedOutputPipe};
+pub use self::pool::{buffer_pool_size, set_buffer_pool_limit};
+pub use self::tuning::{pipe_tuning, set_pipe_tuning, PipeTuning, LOW_MEMORY};
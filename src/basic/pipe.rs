@@ -18,6 +18,7 @@
  */
 use super::buffer::Buffer;
 use super::error::{AnyError, AnyResult};
+use super::instrument::pipeline_event;
 use super::io::Closable;
 use std::io::{Read, Write};
 use std::sync::mpsc::{sync_channel, Receiver, SyncSender};
@@ -27,37 +28,44 @@ use std::sync::mpsc::{sync_channel, Receiver, SyncSender};
 // The Pipe: PipedBufferedOutput --> PipedBufferedInput
 
 // a buffer with data that the output side send to the input side over the channel
-type ConsumerToProducer<T, const SIZE: usize> = (Buffer<T, SIZE>, usize);
+type ConsumerToProducer<T> = (Buffer<T>, usize);
 
 // an empty buffer that the input side send back to the output side over the channel
-type ProducerToConsumer<T, const SIZE: usize> = Buffer<T, SIZE>;
+type ProducerToConsumer<T> = Buffer<T>;
 
 // -----------------------------------------------
 
-// create a buffered pipe that can send things over thread border
+// create a buffered pipe that can send things over thread border, the output side can get up to
+// depth buffers ahead of the input side
 pub fn pipe<T: Default + Copy + Send + 'static, const SIZE: usize>(
+	depth: usize,
 ) -> (BufferedOutputPipe<T, SIZE>, BufferedInputPipe<T, SIZE>) {
+	let depth: usize = depth.max(1);
 	// create 2 sync channel to send and receive buffer
 	let (output_sender, input_receiver): (
-		SyncSender<ConsumerToProducer<T, SIZE>>,
-		Receiver<ConsumerToProducer<T, SIZE>>,
-	) = sync_channel(1);
+		SyncSender<ConsumerToProducer<T>>,
+		Receiver<ConsumerToProducer<T>>,
+	) = sync_channel(depth);
 	let (input_sender, output_receiver): (
-		SyncSender<ProducerToConsumer<T, SIZE>>,
-		Receiver<ProducerToConsumer<T, SIZE>>,
-	) = sync_channel(1);
+		SyncSender<ProducerToConsumer<T>>,
+		Receiver<ProducerToConsumer<T>>,
+	) = sync_channel(depth);
+	// the extra buffers start on their way back to the output side, the channel has room for them
+	for _ in 1..depth {
+		let _cannot_fail_ = input_sender.send(Buffer::new(SIZE));
+	}
 	// create two side of the pipe
 	(
 		BufferedOutputPipe {
 			sender: output_sender,
 			receiver: output_receiver,
-			buffer: Some(Buffer::new()),
+			buffer: Some(Buffer::new(SIZE)),
 			index: 0,
 		},
 		BufferedInputPipe {
 			sender: input_sender,
 			receiver: input_receiver,
-			buffer: Some(Buffer::new()),
+			buffer: Some(Buffer::new(SIZE)),
 			index: 0,
 			length: 0,
 		},
@@ -68,9 +76,9 @@ pub fn pipe<T: Default + Copy + Send + 'static, const SIZE: usize>(
 
 // the output side of the pipe
 pub struct BufferedOutputPipe<T: Copy + Send + 'static, const SIZE: usize> {
-	sender: SyncSender<ConsumerToProducer<T, SIZE>>,
-	receiver: Receiver<ProducerToConsumer<T, SIZE>>,
-	buffer: Option<Buffer<T, SIZE>>,
+	sender: SyncSender<ConsumerToProducer<T>>,
+	receiver: Receiver<ProducerToConsumer<T>>,
+	buffer: Option<Buffer<T>>,
 	index: usize,
 }
 
@@ -80,7 +88,8 @@ impl<T: Copy + Send + 'static, const SIZE: usize> BufferedOutputPipe<T, SIZE> {
 	fn sync(&mut self) -> AnyResult<()> {
 		debug_assert!(self.buffer.is_some());
 		debug_assert!(self.index > 0 && self.index <= SIZE);
-		let buffer: Buffer<T, SIZE> = self.buffer.take().unwrap();
+		let buffer: Buffer<T> = self.buffer.take().unwrap();
+		pipeline_event!(length = self.index, "pipe buffer sent");
 		self.sender.send((buffer, self.index))?;
 		self.buffer = Some(self.receiver.recv()?);
 		self.index = 0;
@@ -147,9 +156,9 @@ impl<T: Copy + Send + 'static, const SIZE: usize> Closable<()> for BufferedOutpu
 
 // the input side of the pipe
 pub struct BufferedInputPipe<T: Copy + Send + 'static, const SIZE: usize> {
-	sender: SyncSender<ProducerToConsumer<T, SIZE>>,
-	receiver: Receiver<ConsumerToProducer<T, SIZE>>,
-	buffer: Option<Buffer<T, SIZE>>,
+	sender: SyncSender<ProducerToConsumer<T>>,
+	receiver: Receiver<ConsumerToProducer<T>>,
+	buffer: Option<Buffer<T>>,
 	length: usize,
 	index: usize,
 }
@@ -160,10 +169,11 @@ impl<T: Copy + Send + 'static, const SIZE: usize> BufferedInputPipe<T, SIZE> {
 	fn sync(&mut self) -> AnyResult<()> {
 		debug_assert!(self.buffer.is_some() && self.index == self.length && self.length <= SIZE);
 		// take the old buffer and set it to None
-		let old_buffer: Buffer<T, SIZE> = self.buffer.take().unwrap();
+		let old_buffer: Buffer<T> = self.buffer.take().unwrap();
 		// receive the new buffer
 		if let Ok((new_buffer, length)) = self.receiver.recv() {
 			debug_assert!(length > 0 && length <= SIZE);
+			pipeline_event!(length, "pipe buffer received");
 			// set the new buffer and its length
 			self.buffer = Some(new_buffer);
 			self.length = length;
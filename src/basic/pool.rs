@@ -0,0 +1,110 @@
+/*
+ * srx: The fast Symbol Ranking based compressor.
+ * Copyright (C) 2023-2024  Mai Thanh Minh (a.k.a. thanhminhmr)
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU General Public License as published by the Free Software
+ * Foundation, either  version 3 of the  License,  or (at your option) any later
+ * version.
+ *
+ * This program  is distributed in the hope  that it will be useful, but WITHOUT
+ * ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+ * FOR  A PARTICULAR PURPOSE. See  the  GNU  General  Public   License  for more
+ * details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+ */
+
+use std::any::{Any, TypeId};
+use std::mem::size_of;
+use std::sync::{Mutex, MutexGuard};
+
+// -----------------------------------------------
+
+// A pool of large boxed slices (pipe buffers and context tables), so that successive encode/decode
+// calls can reuse them instead of allocating and releasing megabytes every time
+pub(super) struct BufferPool {
+	limit: usize,
+	retained: usize,
+	entries: Vec<(TypeId, usize, Box<dyn Any + Send>)>,
+}
+
+impl BufferPool {
+	pub(super) const fn new() -> Self {
+		Self {
+			limit: 0,
+			retained: 0,
+			entries: Vec::new(),
+		}
+	}
+
+	pub(super) fn retained(&self) -> usize {
+		self.retained
+	}
+
+	pub(super) fn set_limit(&mut self, limit: usize) {
+		self.limit = limit;
+		while self.retained > limit {
+			match self.entries.pop() {
+				None => break,
+				Some((_, size, _)) => self.retained -= size,
+			}
+		}
+	}
+
+	// take a slice of the exact type and length out of the pool, if there is one
+	pub(super) fn acquire<T: Copy + Send + 'static>(&mut self, length: usize) -> Option<Box<[T]>> {
+		let position: usize = self.entries.iter().position(|(type_id, size, _)| {
+			*type_id == TypeId::of::<T>() && *size == length * size_of::<T>()
+		})?;
+		let (_, size, slice): (TypeId, usize, Box<dyn Any + Send>) =
+			self.entries.swap_remove(position);
+		self.retained -= size;
+		slice.downcast::<Box<[T]>>().ok().map(|slice| *slice)
+	}
+
+	// keep a slice for later, or drop it if the pool is disabled or full
+	pub(super) fn release<T: Copy + Send + 'static>(&mut self, slice: Box<[T]>) {
+		let size: usize = slice.len() * size_of::<T>();
+		if size != 0 && self.retained + size <= self.limit {
+			self.retained += size;
+			self.entries
+				.push((TypeId::of::<T>(), size, Box::new(slice)));
+		}
+	}
+}
+
+// -----------------------------------------------
+
+// the pool shared by the whole process
+static BUFFER_POOL: Mutex<BufferPool> = Mutex::new(BufferPool::new());
+
+fn lock() -> MutexGuard<'static, BufferPool> {
+	// the pool only holds plain memory, a poisoned lock does not leave it in an invalid state
+	BUFFER_POOL
+		.lock()
+		.unwrap_or_else(|error| error.into_inner())
+}
+
+// Set the maximum number of bytes the pool may keep around. The pool is disabled by default (limit
+// is zero), setting it back to zero releases every retained buffer.
+pub fn set_buffer_pool_limit(limit: usize) {
+	lock().set_limit(limit);
+}
+
+// Number of bytes the pool currently keeps, the buffers released by the codec and not taken again.
+pub fn buffer_pool_size() -> usize {
+	lock().retained()
+}
+
+pub(crate) fn acquire<T: Copy + Send + 'static>(length: usize) -> Option<Box<[T]>> {
+	lock().acquire(length)
+}
+
+pub(crate) fn release<T: Copy + Send + 'static>(slice: Box<[T]>) {
+	if !slice.is_empty() {
+		lock().release(slice);
+	}
+}
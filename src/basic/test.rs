@@ -0,0 +1,55 @@
+/*
+ * srx: The fast Symbol Ranking based compressor.
+ * Copyright (C) 2023-2024  Mai Thanh Minh (a.k.a. thanhminhmr)
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU General Public License as published by the Free Software
+ * Foundation, either  version 3 of the  License,  or (at your option) any later
+ * version.
+ *
+ * This program  is distributed in the hope  that it will be useful, but WITHOUT
+ * ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+ * FOR  A PARTICULAR PURPOSE. See  the  GNU  General  Public   License  for more
+ * details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+ */
+
+use super::pool::BufferPool;
+
+// -----------------------------------------------
+
+// a pool of its own, the one of the process is shared with the other tests
+#[test]
+fn test_buffer_pool_reuse() {
+	let mut pool: BufferPool = BufferPool::new();
+
+	// disabled: nothing is kept
+	pool.release(vec![0_u32; 16].into_boxed_slice());
+	assert!(pool.acquire::<u32>(16).is_none());
+
+	// the same slice is handed out again for the same type and length, once
+	pool.set_limit(1024);
+	let slice: Box<[u32]> = vec![7_u32; 16].into_boxed_slice();
+	let address: *const u32 = slice.as_ptr();
+	pool.release(slice);
+	assert_eq!(pool.retained(), 64);
+	assert!(pool.acquire::<u16>(16).is_none());
+	assert!(pool.acquire::<u32>(8).is_none());
+	let reused: Box<[u32]> = pool.acquire::<u32>(16).unwrap();
+	assert_eq!(reused.as_ptr(), address);
+	assert_eq!(*reused, [7_u32; 16]);
+	assert!(pool.acquire::<u32>(16).is_none());
+	assert_eq!(pool.retained(), 0);
+
+	// a slice over the limit is dropped, lowering the limit drops what is kept
+	pool.release(vec![0_u8; 2048].into_boxed_slice());
+	assert!(pool.acquire::<u8>(2048).is_none());
+	pool.release(vec![0_u8; 512].into_boxed_slice());
+	assert_eq!(pool.retained(), 512);
+	pool.set_limit(0);
+	assert_eq!(pool.retained(), 0);
+	assert!(pool.acquire::<u8>(512).is_none());
+}
@@ -0,0 +1,108 @@
+/*
+ * srx: The fast Symbol Ranking based compressor.
+ * Copyright (C) 2023-2024  Mai Thanh Minh (a.k.a. thanhminhmr)
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU General Public License as published by the Free Software
+ * Foundation, either  version 3 of the  License,  or (at your option) any later
+ * version.
+ *
+ * This program  is distributed in the hope  that it will be useful, but WITHOUT
+ * ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+ * FOR  A PARTICULAR PURPOSE. See  the  GNU  General  Public   License  for more
+ * details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+ */
+
+use super::error::{AnyError, AnyResult};
+use std::sync::{Mutex, MutexGuard};
+
+// -----------------------------------------------
+
+// 32-bit targets (ARMv7 NAS boxes, routers) often have little memory and always a small address
+// space, they get smaller pipes and context tables by default. The low-memory feature picks the
+// same defaults on any target. Only the defaults change, files from other targets still read back.
+pub const LOW_MEMORY: bool = cfg!(any(
+	target_pointer_width = "16",
+	target_pointer_width = "32",
+	feature = "low-memory"
+));
+
+// -----------------------------------------------
+
+/// Sizes of the pipes between the threads of a compression or decompression. They change the
+/// speed and the memory taken, never the compressed data.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct PipeTuning {
+	/// Bytes in each buffer of the pipes carrying the input and the output.
+	pub io_buffer_size: usize,
+	/// Messages in each buffer of the pipe between the two context threads.
+	pub message_buffer_size: usize,
+	/// Number of full buffers a thread may get ahead of the next one.
+	pub pipe_depth: usize,
+}
+
+impl PipeTuning {
+	/// The buffer sizes the codec is built for.
+	pub const IO_BUFFER_SIZES: [usize; 3] = [0x40000, 0x100000, 0x400000];
+	pub const MESSAGE_BUFFER_SIZES: [usize; 2] = [0x10000, 0x40000];
+	/// The deepest pipes allowed.
+	pub const MAX_PIPE_DEPTH: usize = 8;
+
+	/// The smallest pipes, about 1.5 MiB instead of 18 MiB for a compression.
+	pub const LOW_MEMORY: PipeTuning = PipeTuning {
+		io_buffer_size: 0x40000,
+		message_buffer_size: 0x10000,
+		pipe_depth: 1,
+	};
+
+	/// The pipes used until the process is tuned, the smallest ones on low memory targets.
+	pub const DEFAULT: PipeTuning = if LOW_MEMORY {
+		Self::LOW_MEMORY
+	} else {
+		PipeTuning {
+			io_buffer_size: 0x400000,
+			message_buffer_size: 0x40000,
+			pipe_depth: 1,
+		}
+	};
+
+	pub fn is_valid(&self) -> bool {
+		Self::IO_BUFFER_SIZES.contains(&self.io_buffer_size)
+			&& Self::MESSAGE_BUFFER_SIZES.contains(&self.message_buffer_size)
+			&& (1..=Self::MAX_PIPE_DEPTH).contains(&self.pipe_depth)
+	}
+}
+
+impl Default for PipeTuning {
+	fn default() -> Self {
+		Self::DEFAULT
+	}
+}
+
+// -----------------------------------------------
+
+static PIPE_TUNING: Mutex<PipeTuning> = Mutex::new(PipeTuning::DEFAULT);
+
+fn lock() -> MutexGuard<'static, PipeTuning> {
+	PIPE_TUNING
+		.lock()
+		.unwrap_or_else(|error| error.into_inner())
+}
+
+/// Set the pipe sizes used by every later compression and decompression in this process.
+pub fn set_pipe_tuning(tuning: PipeTuning) -> AnyResult<()> {
+	if !tuning.is_valid() {
+		return Err(AnyError::from_string("Unsupported pipe tuning!"));
+	}
+	*lock() = tuning;
+	Ok(())
+}
+
+/// The pipe sizes currently in use.
+pub fn pipe_tuning() -> PipeTuning {
+	*lock()
+}
@@ -0,0 +1,184 @@
+/*
+ * srx: The fast Symbol Ranking based compressor.
+ * Copyright (C) 2023-2024  Mai Thanh Minh (a.k.a. thanhminhmr)
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU General Public License as published by the Free Software
+ * Foundation, either  version 3 of the  License,  or (at your option) any later
+ * version.
+ *
+ * This program  is distributed in the hope  that it will be useful, but WITHOUT
+ * ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+ * FOR  A PARTICULAR PURPOSE. See  the  GNU  General  Public   License  for more
+ * details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+ */
+
+use std::io::{Read, Result, Write};
+use xxhash_rust::xxh3::Xxh3;
+
+// -----------------------------------------------
+
+/// Integrity check of the original data, stored at the end of the compressed stream.
+///
+/// Stronger checksums take longer to compute, but they are computed on the reader and writer
+/// threads, so they only slow down the compression when they are slower than the model itself.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Default)]
+pub enum Checksum {
+	/// No checksum, corruption may go unnoticed.
+	#[default]
+	None,
+	/// CRC-32 (IEEE), catches accidental corruption.
+	Crc32,
+	/// 64 bits XXH3, catches accidental corruption with a much lower chance of a miss.
+	Xxh3,
+	/// 256 bits BLAKE3, a cryptographic hash.
+	Blake3,
+}
+
+impl Checksum {
+	/// All checksums, in the order of their identifiers.
+	pub const ALL: [Checksum; 4] = [Self::None, Self::Crc32, Self::Xxh3, Self::Blake3];
+
+	/// The name used on the command line.
+	pub fn name(self) -> &'static str {
+		match self {
+			Self::None => "none",
+			Self::Crc32 => "crc32",
+			Self::Xxh3 => "xxh3",
+			Self::Blake3 => "blake3",
+		}
+	}
+
+	/// Find a checksum by its name.
+	pub fn from_name(name: &str) -> Option<Self> {
+		Self::ALL
+			.into_iter()
+			.find(|checksum| checksum.name() == name)
+	}
+
+	/// Length of the digest in bytes.
+	pub fn digest_length(self) -> usize {
+		match self {
+			Self::None => 0,
+			Self::Crc32 => 4,
+			Self::Xxh3 => 8,
+			Self::Blake3 => 32,
+		}
+	}
+
+	// the identifier stored in the file header
+	pub(crate) fn id(self) -> u8 {
+		self as u8
+	}
+
+	pub(crate) fn from_id(id: u8) -> Option<Self> {
+		Self::ALL.get(id as usize).copied()
+	}
+
+	fn hasher(self) -> Hasher {
+		match self {
+			Self::None => Hasher::None,
+			Self::Crc32 => Hasher::Crc32(crc32fast::Hasher::new()),
+			Self::Xxh3 => Hasher::Xxh3(Box::new(Xxh3::new())),
+			Self::Blake3 => Hasher::Blake3(Box::new(blake3::Hasher::new())),
+		}
+	}
+}
+
+// -----------------------------------------------
+
+enum Hasher {
+	None,
+	Crc32(crc32fast::Hasher),
+	Xxh3(Box<Xxh3>),
+	Blake3(Box<blake3::Hasher>),
+}
+
+impl Hasher {
+	fn update(&mut self, data: &[u8]) {
+		match self {
+			Self::None => {}
+			Self::Crc32(hasher) => hasher.update(data),
+			Self::Xxh3(hasher) => hasher.update(data),
+			Self::Blake3(hasher) => {
+				hasher.update(data);
+			}
+		}
+	}
+
+	// the digest, big endian for the integer checksums so it reads the same as their usual hex
+	fn finish(self) -> Vec<u8> {
+		match self {
+			Self::None => Vec::new(),
+			Self::Crc32(hasher) => hasher.finalize().to_be_bytes().to_vec(),
+			Self::Xxh3(hasher) => hasher.digest().to_be_bytes().to_vec(),
+			Self::Blake3(hasher) => hasher.finalize().as_bytes().to_vec(),
+		}
+	}
+}
+
+// -----------------------------------------------
+
+// a reader that hashes everything read through it
+pub(crate) struct DigestReader<R: Read> {
+	reader: R,
+	hasher: Hasher,
+}
+
+impl<R: Read> DigestReader<R> {
+	pub(crate) fn new(reader: R, checksum: Checksum) -> Self {
+		Self {
+			reader,
+			hasher: checksum.hasher(),
+		}
+	}
+
+	pub(crate) fn finish(self) -> (R, Vec<u8>) {
+		(self.reader, self.hasher.finish())
+	}
+}
+
+impl<R: Read> Read for DigestReader<R> {
+	fn read(&mut self, buffer: &mut [u8]) -> Result<usize> {
+		let length: usize = self.reader.read(buffer)?;
+		self.hasher.update(&buffer[..length]);
+		Ok(length)
+	}
+}
+
+// -----------------------------------------------
+
+// a writer that hashes everything written through it
+pub(crate) struct DigestWriter<W: Write> {
+	writer: W,
+	hasher: Hasher,
+}
+
+impl<W: Write> DigestWriter<W> {
+	pub(crate) fn new(writer: W, checksum: Checksum) -> Self {
+		Self {
+			writer,
+			hasher: checksum.hasher(),
+		}
+	}
+
+	pub(crate) fn finish(self) -> (W, Vec<u8>) {
+		(self.writer, self.hasher.finish())
+	}
+}
+
+impl<W: Write> Write for DigestWriter<W> {
+	fn write(&mut self, buffer: &[u8]) -> Result<usize> {
+		let length: usize = self.writer.write(buffer)?;
+		self.hasher.update(&buffer[..length]);
+		Ok(length)
+	}
+
+	fn flush(&mut self) -> Result<()> {
+		self.writer.flush()
+	}
+}
@@ -0,0 +1,170 @@
+/*
+ * srx: The fast Symbol Ranking based compressor.
+ * Copyright (C) 2023-2024  Mai Thanh Minh (a.k.a. thanhminhmr)
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU General Public License as published by the Free Software
+ * Foundation, either  version 3 of the  License,  or (at your option) any later
+ * version.
+ *
+ * This program  is distributed in the hope  that it will be useful, but WITHOUT
+ * ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+ * FOR  A PARTICULAR PURPOSE. See  the  GNU  General  Public   License  for more
+ * details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+ */
+
+use super::options::Options;
+use super::single::{report, run};
+use super::stealing::{run_stealing, MemoryBudget, MemoryLease, Worker};
+use srx::{inspect, memory_usage, AnyError, AnyResult, Config, MatchStats};
+use std::ffi::OsString;
+use std::fs::{metadata, read_dir, DirEntry, File, FileType};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+
+// -----------------------------------------------
+
+const SUFFIX: &str = "srx";
+
+// -----------------------------------------------
+
+enum Job {
+	Directory(PathBuf),
+	File(PathBuf),
+}
+
+// the totals of all processed files
+#[derive(Default)]
+struct Summary {
+	files: u64,
+	failed: u64,
+	input_size: u64,
+	output_size: u64,
+}
+
+// -----------------------------------------------
+
+// a file found in a directory is only picked if it is in the right format for the direction
+fn is_selected(path: &Path, is_compress: bool) -> bool {
+	let is_compressed: bool = path
+		.extension()
+		.is_some_and(|extension| extension == SUFFIX);
+	is_compress != is_compressed
+}
+
+fn output_path(path: &Path, is_compress: bool) -> AnyResult<PathBuf> {
+	if is_compress {
+		let mut name: OsString = path.as_os_str().to_owned();
+		name.push(".");
+		name.push(SUFFIX);
+		Ok(PathBuf::from(name))
+	} else if !is_selected(path, false) {
+		Err(AnyError::from_string("Not a .srx file!"))
+	} else {
+		Ok(path.with_extension(""))
+	}
+}
+
+fn walk(worker: &Worker<Job>, path: &Path, is_compress: bool) -> AnyResult<()> {
+	for entry in read_dir(path)? {
+		let entry: DirEntry = entry?;
+		let file_type: FileType = entry.file_type()?;
+		if file_type.is_dir() {
+			worker.push(Job::Directory(entry.path()));
+		} else if file_type.is_file() && is_selected(&entry.path(), is_compress) {
+			worker.push(Job::File(entry.path()));
+		}
+	}
+	Ok(())
+}
+
+// compression sizes its tables for the file, decompression takes the sizes recorded in the header
+pub fn memory(path: &Path, options: &Options) -> AnyResult<usize> {
+	let config: Config = if options.is_compress {
+		Config {
+			content_size: Some(metadata(path)?.len()),
+			..options.config.clone()
+		}
+	} else {
+		inspect(File::open(path)?)?.config
+	};
+	Ok(memory_usage(&config))
+}
+
+fn process(path: &Path, options: &Options, budget: &MemoryBudget) -> AnyResult<(u64, u64, f64)> {
+	let output: PathBuf = output_path(path, options.is_compress)?;
+	let _lease: MemoryLease = budget.acquire(memory(path, options)?);
+	let (_, input_size, output_size, duration, _): (bool, u64, u64, f64, Option<MatchStats>) =
+		run(path, &output, Some(options.is_compress), options)?;
+	Ok((input_size, output_size, duration))
+}
+
+fn lock(summary: &Mutex<Summary>) -> MutexGuard<'_, Summary> {
+	summary.lock().unwrap_or_else(|error| error.into_inner())
+}
+
+fn failed(summary: &Mutex<Summary>, path: &Path, error: AnyError) {
+	lock(summary).failed += 1;
+	println!("{}: Error occurred! {}", path.display(), error);
+}
+
+// -----------------------------------------------
+
+pub fn run_batch(options: &Options) -> bool {
+	let mut jobs: Vec<Job> = Vec::new();
+	for path in options.paths.iter() {
+		if !path.is_dir() {
+			jobs.push(Job::File(path.clone()));
+		} else if options.recursive {
+			jobs.push(Job::Directory(path.clone()));
+		} else {
+			println!(
+				"{}: Is a directory, use -r to walk into it!",
+				path.display()
+			);
+			return false;
+		}
+	}
+
+	let budget: MemoryBudget = MemoryBudget::new(options.memory_budget);
+	let summary: Mutex<Summary> = Mutex::new(Summary::default());
+
+	run_stealing(
+		options.threads,
+		jobs,
+		|worker: &Worker<Job>, job: Job| match job {
+			Job::Directory(path) => {
+				if let Err(error) = walk(worker, &path, options.is_compress) {
+					failed(&summary, &path, error);
+				}
+			}
+			Job::File(path) => match process(&path, options, &budget) {
+				Ok((input_size, output_size, duration)) => {
+					let mut summary: MutexGuard<Summary> = lock(&summary);
+					summary.files += 1;
+					summary.input_size += input_size;
+					summary.output_size += output_size;
+					println!(
+						"{}: {}",
+						path.display(),
+						report(input_size, output_size, duration, options.is_compress)
+					);
+				}
+				Err(error) => failed(&summary, &path, error),
+			},
+		},
+	);
+
+	let summary: Summary = summary
+		.into_inner()
+		.unwrap_or_else(|error| error.into_inner());
+	println!(
+		"{} files done, {} failed, {} -> {} bytes in total",
+		summary.files, summary.failed, summary.input_size, summary.output_size
+	);
+	summary.failed == 0
+}
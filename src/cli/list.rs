@@ -0,0 +1,69 @@
+/*
+ * srx: The fast Symbol Ranking based compressor.
+ * Copyright (C) 2023-2024  Mai Thanh Minh (a.k.a. thanhminhmr)
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU General Public License as published by the Free Software
+ * Foundation, either  version 3 of the  License,  or (at your option) any later
+ * version.
+ *
+ * This program  is distributed in the hope  that it will be useful, but WITHOUT
+ * ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+ * FOR  A PARTICULAR PURPOSE. See  the  GNU  General  Public   License  for more
+ * details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+ */
+
+use srx::{inspect, AnyResult, ContextSize, FrameInfo};
+use std::fs::File;
+
+// -----------------------------------------------
+
+pub fn describe(path: &str) -> AnyResult<String> {
+	let info: FrameInfo = inspect(File::open(path)?)?;
+	// an empty input has no ratio to show
+	let sizes: String = match info.original_size() {
+		Some(0) => format!("0 -> {}", info.compressed_size),
+		Some(original_size) => format!(
+			"{} -> {} ({:.2}%)",
+			original_size,
+			info.compressed_size,
+			info.compressed_size as f64 / original_size as f64 * 100.0
+		),
+		None => format!("unknown -> {}", info.compressed_size),
+	};
+	let context_size: ContextSize = info.config.resolved_context_size();
+	let blocks: String = match info.config.block_size {
+		Some(block_size) => format!(", {} blocks of {}", info.blocks.len(), block_size),
+		None => String::new(),
+	};
+	Ok(format!(
+		"version {}, profile {}, checksum {}, context {}/{} bits{}, {}",
+		info.version,
+		info.config.profile.name(),
+		info.config.checksum.name(),
+		context_size.primary_bits,
+		context_size.literal_bits,
+		blocks,
+		sizes
+	))
+}
+
+// -----------------------------------------------
+
+pub fn run_list(paths: &[String]) -> bool {
+	let mut is_success: bool = true;
+	for path in paths {
+		match describe(path) {
+			Ok(description) => println!("{}: {}", path, description),
+			Err(error) => {
+				println!("{}: Error occurred! {}", path, error);
+				is_success = false;
+			}
+		}
+	}
+	is_success
+}
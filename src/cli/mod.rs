@@ -0,0 +1,45 @@
+/*
+ * srx: The fast Symbol Ranking based compressor.
+ * Copyright (C) 2023-2024  Mai Thanh Minh (a.k.a. thanhminhmr)
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU General Public License as published by the Free Software
+ * Foundation, either  version 3 of the  License,  or (at your option) any later
+ * version.
+ *
+ * This program  is distributed in the hope  that it will be useful, but WITHOUT
+ * ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+ * FOR  A PARTICULAR PURPOSE. See  the  GNU  General  Public   License  for more
+ * details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+ */
+
+mod batch;
+mod list;
+mod network;
+mod options;
+mod recover;
+mod selftest;
+mod settings;
+mod single;
+mod stealing;
+mod stream;
+mod tune;
+mod verify;
+
+#[cfg(test)]
+mod test;
+
+pub use self::batch::run_batch;
+pub use self::list::run_list;
+pub use self::network::run_network;
+pub use self::options::{help, parse};
+pub use self::recover::run_recover;
+pub use self::selftest::run_selftest;
+pub use self::settings::apply_settings;
+pub use self::single::run_single;
+pub use self::tune::run_tune;
+pub use self::verify::run_verify;
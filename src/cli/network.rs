@@ -0,0 +1,152 @@
+/*
+ * srx: The fast Symbol Ranking based compressor.
+ * Copyright (C) 2023-2024  Mai Thanh Minh (a.k.a. thanhminhmr)
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU General Public License as published by the Free Software
+ * Foundation, either  version 3 of the  License,  or (at your option) any later
+ * version.
+ *
+ * This program  is distributed in the hope  that it will be useful, but WITHOUT
+ * ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+ * FOR  A PARTICULAR PURPOSE. See  the  GNU  General  Public   License  for more
+ * details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+ */
+
+use super::options::{Endpoint, Options};
+use super::single::report;
+use super::stream::{open_input, open_output, Counter};
+use srx::{decompress, AnyResult, Config, SrxStreamWriter};
+use std::io::{ErrorKind, Read, Write};
+use std::net::{Shutdown, TcpListener, TcpStream};
+use std::path::Path;
+use std::sync::mpsc::{sync_channel, Receiver, RecvTimeoutError, SyncSender};
+use std::thread::spawn;
+use std::time::{Duration, Instant};
+
+// -----------------------------------------------
+
+// the longest time read data waits before it is sent, in a block of its own if need be
+const FLUSH_INTERVAL: Duration = Duration::from_millis(100);
+const READ_SIZE: usize = 0x10000;
+const READ_DEPTH: usize = 16;
+
+// a piece of the input as it was read
+type Chunk = AnyResult<Vec<u8>>;
+
+// -----------------------------------------------
+
+fn open_stream(endpoint: &Endpoint) -> AnyResult<TcpStream> {
+	match endpoint {
+		Endpoint::Listen(address) => {
+			let listener: TcpListener = TcpListener::bind(address)?;
+			let (stream, _) = listener.accept()?;
+			Ok(stream)
+		}
+		Endpoint::Connect(address) => Ok(TcpStream::connect(address.as_str())?),
+	}
+}
+
+// read the input on a thread of its own, so that waiting for it can time out
+fn read_chunks<R: Read + Send + 'static>(mut input: R) -> Receiver<Chunk> {
+	let (sender, receiver): (SyncSender<Chunk>, Receiver<Chunk>) = sync_channel(READ_DEPTH);
+	spawn(move || loop {
+		let mut chunk: Vec<u8> = vec![0; READ_SIZE];
+		let chunk: Chunk = match input.read(&mut chunk) {
+			Ok(0) => return,
+			Ok(length) => {
+				chunk.truncate(length);
+				Ok(chunk)
+			}
+			Err(error) if error.kind() == ErrorKind::Interrupted => continue,
+			Err(error) => Err(error.into()),
+		};
+		// stop at the first error, or once nobody is waiting for the input anymore
+		let is_error: bool = chunk.is_err();
+		if sender.send(chunk).is_err() || is_error {
+			return;
+		}
+	});
+	receiver
+}
+
+// Compress the input to the socket as a blocked srx stream. The data is sent at the latest
+// FLUSH_INTERVAL after it is read, a pause of the input ends the current block early instead of
+// holding the data until the block is full.
+pub fn send<R: Read + Send + 'static>(
+	input: R,
+	stream: TcpStream,
+	config: &Config,
+) -> AnyResult<(u64, u64)> {
+	let chunks: Receiver<Chunk> = read_chunks(input);
+	let mut writer: SrxStreamWriter<Counter<TcpStream>> =
+		SrxStreamWriter::new(Counter::new(stream), config)?;
+	let mut input_size: u64 = 0;
+	// when the oldest data not sent yet was read
+	let mut pending: Option<Instant> = None;
+	loop {
+		let chunk: Result<Chunk, RecvTimeoutError> = match pending {
+			None => chunks.recv().map_err(|_| RecvTimeoutError::Disconnected),
+			Some(since) => chunks.recv_timeout(FLUSH_INTERVAL.saturating_sub(since.elapsed())),
+		};
+		match chunk {
+			Ok(chunk) => {
+				let chunk: Vec<u8> = chunk?;
+				writer.write_all(&chunk)?;
+				input_size += chunk.len() as u64;
+				pending.get_or_insert_with(Instant::now);
+			}
+			Err(RecvTimeoutError::Timeout) => {
+				writer.flush()?;
+				pending = None;
+			}
+			Err(RecvTimeoutError::Disconnected) => break,
+		}
+	}
+	let stream: Counter<TcpStream> = writer.finish()?;
+	// let the other side know that the stream is over
+	stream.get_ref().shutdown(Shutdown::Write)?;
+	Ok((input_size, stream.count()))
+}
+
+// decompress from the socket to the output, each block is written out as soon as it arrives
+pub fn receive<W: Write + Send>(stream: TcpStream, output: W) -> AnyResult<(u64, u64)> {
+	let (stream, mut output): (Counter<TcpStream>, Counter<W>) =
+		decompress(Counter::new(stream), Counter::new(output))?;
+	output.flush()?;
+	Ok((stream.count(), output.count()))
+}
+
+// the compressed side is always the socket: compress a file to it, or decompress from it to a file
+fn run(options: &Options, endpoint: &Endpoint) -> AnyResult<(u64, u64, f64)> {
+	let path: &Path = &options.paths[0];
+	let stream: TcpStream = open_stream(endpoint)?;
+	let start: Instant = Instant::now();
+	let (input_size, output_size): (u64, u64) = if options.is_compress {
+		send(open_input(path)?, stream, &options.config)?
+	} else {
+		receive(stream, open_output(path)?)?
+	};
+	Ok((input_size, output_size, start.elapsed().as_secs_f64()))
+}
+
+pub fn run_network(options: &Options, endpoint: &Endpoint) -> bool {
+	// the output may be the standard output, report on the standard error
+	match run(options, endpoint) {
+		Ok((input_size, output_size, duration)) => {
+			eprintln!(
+				"{}",
+				report(input_size, output_size, duration, options.is_compress)
+			);
+			true
+		}
+		Err(error) => {
+			eprintln!("Error occurred! {}", error);
+			false
+		}
+	}
+}
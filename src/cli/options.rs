@@ -0,0 +1,241 @@
+/*
+ * srx: The fast Symbol Ranking based compressor.
+ * Copyright (C) 2023-2024  Mai Thanh Minh (a.k.a. thanhminhmr)
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU General Public License as published by the Free Software
+ * Foundation, either  version 3 of the  License,  or (at your option) any later
+ * version.
+ *
+ * This program  is distributed in the hope  that it will be useful, but WITHOUT
+ * ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+ * FOR  A PARTICULAR PURPOSE. See  the  GNU  General  Public   License  for more
+ * details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+ */
+
+use super::settings::settings;
+use srx::secondary_context::AdaptationProfile;
+use srx::{Checksum, Config};
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::path::{Path, PathBuf};
+use std::process::exit;
+use std::slice::Iter;
+use std::str::FromStr;
+use std::thread::available_parallelism;
+
+// -----------------------------------------------
+
+const DEFAULT_MEMORY_BUDGET: usize = 1024 << 20;
+const DEFAULT_SYNC_BLOCK_SIZE: u64 = 4 << 20;
+
+// -----------------------------------------------
+
+pub enum Endpoint {
+	Listen(SocketAddr),
+	Connect(String),
+}
+
+pub struct Options {
+	pub is_compress: bool,
+	// no command nor -z/-d: the direction is found from the input
+	pub is_auto: bool,
+	// report how the bytes matched after compressing
+	pub stats: bool,
+	// each path is an input compressed next to itself, even when there are two of them
+	pub batch: bool,
+	pub config: Config,
+	pub paths: Vec<PathBuf>,
+	pub recursive: bool,
+	pub threads: usize,
+	pub memory_budget: usize,
+	pub endpoint: Option<Endpoint>,
+}
+
+impl Options {
+	// a single input and output file, or a batch of files each compressed to a file next to it
+	pub fn is_batch(&self) -> bool {
+		self.batch || self.recursive
+	}
+}
+
+// -----------------------------------------------
+
+pub fn help() -> ! {
+	println!(
+		"\
+		srx: The fast Symbol Ranking based compressor, version {}.\n\
+		Copyright (C) 2023  Mai Thanh Minh (a.k.a. thanhminhmr)\n\n\
+		To   compress: srx c [options] <input-file> <output-file>\n\
+		To decompress: srx d [options] <input-file> <output-file>\n\
+		To decompress srx files and compress anything else: srx [-z | -d] [options] <input-file> <output-file>\n\n\
+		To   compress many files: srx c [options] (--batch | -r) <file-or-directory>...\n\
+		To decompress many files: srx d [options] (--batch | -r) <file-or-directory>...\n\
+		Each file is compressed to <file>.srx, or decompressed from <file>.srx to <file>.\n\n\
+		To   compress to a socket: srx c [options] (--listen <port> [--bind <address>] | --connect <host:port>) <input-file>\n\
+		To decompress from a socket: srx d (--listen <port> [--bind <address>] | --connect <host:port>) <output-file>\n\
+		A file can be - for the standard input or output.\n\n\
+		To list compressed files without decompressing: srx -l <file>...\n\
+		To check compressed files without writing anything: srx verify <file>...\n\
+		To recover what is left of a damaged file compressed with -s: srx recover <input-file> <output-file>\n\
+		To check that this build works: srx selftest\n\
+		To find the fastest defaults for this machine: srx tune\n\n\
+		Options:\n\
+		\x20 -z, --compress          compress, whatever the input is\n\
+		\x20 -d, --decompress        decompress, whatever the input is\n\
+		\x20 -p, --profile <name>    adaptation profile: normal (default), fast, slow, stationary\n\
+		\x20 -c, --checksum <name>   checksum: none (default), crc32, xxh3, blake3\n\
+		\x20 -b, --block-size <MiB>  compress in independent blocks, to allow reading at any place\n\
+		\x20 -s, --sync              mark each block to allow recovering damaged files (blocks of {} MiB by default)\n\
+		\x20     --stats             show how the bytes matched, for a single file compressed to a file\n\
+		\x20     --batch             take every path as an input, even when there are two\n\
+		\x20 -r, --recursive         take every path as an input and walk into directories\n\
+		\x20 -t, --threads <n>       number of files processed at the same time (default: {})\n\
+		\x20 -m, --memory <MiB>      memory budget shared by these files (default: {})\n\
+		\x20     --listen <port>     wait for a connection on the port\n\
+		\x20     --bind <address>    listen on this address instead of 127.0.0.1, anyone reaching it can connect\n\
+		\x20     --connect <address> connect to the address",
+		env!("CARGO_PKG_VERSION"),
+		DEFAULT_SYNC_BLOCK_SIZE >> 20,
+		default_threads(),
+		DEFAULT_MEMORY_BUDGET >> 20,
+	);
+	exit(0);
+}
+
+// the number found by srx tune, or one per core
+fn default_threads() -> usize {
+	settings()
+		.threads
+		.unwrap_or_else(|| available_parallelism().map_or(1, |threads| threads.get()))
+}
+
+fn value<T: FromStr>(iter: &mut Iter<String>) -> T {
+	match iter.next().and_then(|value| value.parse().ok()) {
+		Some(value) => value,
+		None => help(),
+	}
+}
+
+// a size given in MiB, one too large for a u64 is refused rather than losing its high bits
+fn mebibytes(iter: &mut Iter<String>) -> u64 {
+	match value::<u64>(iter).checked_mul(1 << 20) {
+		Some(size) => size,
+		None => help(),
+	}
+}
+
+pub fn parse(args: &[String]) -> Options {
+	// check and parse arguments
+	if args.len() < 3 {
+		help()
+	}
+	// without a command the direction is given by -z/-d, or found from the input
+	let (command, args): (Option<bool>, &[String]) = match args[1].as_str() {
+		"c" => (Some(true), &args[2..]),
+		"d" => (Some(false), &args[2..]),
+		// a word naming no file is a mistyped command rather than an input
+		word if word.chars().all(|c| c.is_ascii_alphabetic()) && !Path::new(word).exists() => {
+			help()
+		}
+		_ => (None, &args[1..]),
+	};
+	let direction: Option<bool> = command.or_else(|| {
+		args.iter().rev().find_map(|arg| match arg.as_str() {
+			"-z" | "--compress" => Some(true),
+			"-d" | "--decompress" => Some(false),
+			_ => None,
+		})
+	});
+	// the options of compression are taken as long as the input may be compressed
+	let is_compress: bool = direction != Some(false);
+	let mut options: Options = Options {
+		is_compress,
+		is_auto: direction.is_none(),
+		stats: false,
+		batch: false,
+		config: Config::default(),
+		paths: Vec::new(),
+		recursive: false,
+		threads: default_threads(),
+		memory_budget: DEFAULT_MEMORY_BUDGET,
+		endpoint: None,
+	};
+	let mut bind: Option<IpAddr> = None;
+	let mut iter: Iter<String> = args.iter();
+	while let Some(arg) = iter.next() {
+		match arg.as_str() {
+			"-z" | "--compress" | "-d" | "--decompress" if command.is_none() => {}
+			"-p" | "--profile" if is_compress => {
+				options.config.profile = match iter
+					.next()
+					.and_then(|name| AdaptationProfile::from_name(name))
+				{
+					Some(profile) => profile,
+					None => help(),
+				}
+			}
+			"-c" | "--checksum" if is_compress => {
+				options.config.checksum =
+					match iter.next().and_then(|name| Checksum::from_name(name)) {
+						Some(checksum) => checksum,
+						None => help(),
+					}
+			}
+			"-b" | "--block-size" if is_compress => {
+				options.config.block_size = Some(mebibytes(&mut iter).max(1 << 20))
+			}
+			"-s" | "--sync" if is_compress => options.config.sync_markers = true,
+			"--stats" if is_compress => options.stats = true,
+			"--batch" => options.batch = true,
+			"-r" | "--recursive" => options.recursive = true,
+			"-t" | "--threads" => options.threads = value::<usize>(&mut iter).max(1),
+			"-m" | "--memory" => {
+				options.memory_budget = match usize::try_from(mebibytes(&mut iter)) {
+					Ok(size) => size,
+					Err(_) => help(),
+				}
+			}
+			"--listen" => {
+				options.endpoint = Some(Endpoint::Listen(SocketAddr::new(
+					IpAddr::V4(Ipv4Addr::LOCALHOST),
+					value(&mut iter),
+				)))
+			}
+			"--bind" => bind = Some(value::<IpAddr>(&mut iter)),
+			"--connect" => options.endpoint = Some(Endpoint::Connect(value(&mut iter))),
+			_ if arg.starts_with('-') && arg != "-" => help(),
+			_ => options.paths.push(PathBuf::from(arg)),
+		}
+	}
+	// only the local host can connect, unless another address to listen on is given
+	match (&mut options.endpoint, bind) {
+		(_, None) => {}
+		(Some(Endpoint::Listen(address)), Some(bind)) => address.set_ip(bind),
+		_ => help(),
+	}
+	// without --batch or -r two paths are an input and an output, as they always were
+	let paths: usize = match (&options.endpoint, options.is_batch()) {
+		(Some(_), _) => 1,
+		(None, false) => 2,
+		(None, true) => options.paths.len().max(1),
+	};
+	if options.paths.len() != paths {
+		help()
+	}
+	// only a single input can be looked at before picking the direction
+	if options.is_auto && (options.is_batch() || options.endpoint.is_some()) {
+		help()
+	}
+	// the statistics are only counted by a single compression with a header
+	if options.stats && (options.is_batch() || options.endpoint.is_some()) {
+		help()
+	}
+	if options.config.sync_markers && options.config.block_size.is_none() {
+		options.config.block_size = Some(DEFAULT_SYNC_BLOCK_SIZE);
+	}
+	options
+}
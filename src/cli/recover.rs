@@ -0,0 +1,61 @@
+/*
+ * srx: The fast Symbol Ranking based compressor.
+ * Copyright (C) 2023-2024  Mai Thanh Minh (a.k.a. thanhminhmr)
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU General Public License as published by the Free Software
+ * Foundation, either  version 3 of the  License,  or (at your option) any later
+ * version.
+ *
+ * This program  is distributed in the hope  that it will be useful, but WITHOUT
+ * ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+ * FOR  A PARTICULAR PURPOSE. See  the  GNU  General  Public   License  for more
+ * details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+ */
+
+use srx::{recover, AnyResult, RecoveryReport};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+
+// -----------------------------------------------
+
+fn run(input_path: &str, output_path: &str) -> AnyResult<RecoveryReport> {
+	let reader: File = File::open(input_path)?;
+	let writer: BufWriter<File> = BufWriter::new(File::create(output_path)?);
+	let (_, mut writer, report): (File, BufWriter<File>, RecoveryReport) = recover(reader, writer)?;
+	writer.flush()?;
+	Ok(report)
+}
+
+// -----------------------------------------------
+
+pub fn run_recover(args: &[String]) -> bool {
+	match run(&args[0], &args[1]) {
+		Ok(report) => {
+			println!(
+				"{} blocks recovered, {} bytes",
+				report.blocks, report.recovered_size
+			);
+			for range in &report.lost {
+				println!(
+					"lost: {}..{} ({} bytes)",
+					range.start,
+					range.end,
+					range.end - range.start
+				);
+			}
+			if report.original_size.is_none() {
+				println!("lost: maybe some after the last block, the original size is unknown");
+			}
+			report.lost.is_empty() && report.original_size.is_some()
+		}
+		Err(error) => {
+			println!("Error occurred! {}", error);
+			false
+		}
+	}
+}
@@ -0,0 +1,114 @@
+/*
+ * srx: The fast Symbol Ranking based compressor.
+ * Copyright (C) 2023-2024  Mai Thanh Minh (a.k.a. thanhminhmr)
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU General Public License as published by the Free Software
+ * Foundation, either  version 3 of the  License,  or (at your option) any later
+ * version.
+ *
+ * This program  is distributed in the hope  that it will be useful, but WITHOUT
+ * ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+ * FOR  A PARTICULAR PURPOSE. See  the  GNU  General  Public   License  for more
+ * details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+ */
+
+use srx::secondary_context::AdaptationProfile;
+use srx::{compress, decompress, AnyError, AnyResult, Checksum, Config};
+use std::io::Cursor;
+
+// -----------------------------------------------
+
+// a bit of real text, the license this program is shipped under
+pub const TEXT_CORPUS: &str = include_str!("../../LICENSE");
+
+// -----------------------------------------------
+
+pub fn random_data(length: usize, mut seed: u64) -> Vec<u8> {
+	// xorshift64, good enough to be incompressible
+	let mut data: Vec<u8> = Vec::with_capacity(length);
+	while data.len() < length {
+		seed ^= seed << 13;
+		seed ^= seed >> 7;
+		seed ^= seed << 17;
+		data.push((seed >> 32) as u8);
+	}
+	data
+}
+
+fn round_trip(data: &[u8], config: &Config) -> AnyResult<usize> {
+	// the size is recorded too, to have it checked on both ends
+	let config: Config = Config {
+		content_size: Some(data.len() as u64),
+		..config.clone()
+	};
+	let (_, compressed): (_, Cursor<Vec<u8>>) =
+		compress(Cursor::new(data), Cursor::new(Vec::new()), &config)?;
+	let compressed: Vec<u8> = compressed.into_inner();
+	let (_, decompressed): (_, Cursor<Vec<u8>>) =
+		decompress(Cursor::new(compressed.as_slice()), Cursor::new(Vec::new()))?;
+	if decompressed.into_inner() != data {
+		return Err(AnyError::from_string(
+			"Decompressed data does not match the original!",
+		));
+	}
+	Ok(compressed.len())
+}
+
+// -----------------------------------------------
+
+pub fn run_selftest() -> bool {
+	let cases: [(&str, Vec<u8>); 6] = [
+		("empty", Vec::new()),
+		("single byte", vec![0x42]),
+		("single zero byte", vec![0]),
+		("repeated byte", vec![0xFF; 0x10000]),
+		("random data", random_data(0x100000, 0x2545F4914F6CDD1D)),
+		("text corpus", TEXT_CORPUS.as_bytes().repeat(4)),
+	];
+	// every profile and every checksum, paired up to keep the run short, then the blocked layout
+	let mut configs: Vec<Config> = AdaptationProfile::ALL
+		.into_iter()
+		.zip(Checksum::ALL)
+		.map(|(profile, checksum)| Config {
+			profile,
+			checksum,
+			..Config::default()
+		})
+		.collect();
+	configs.push(Config {
+		checksum: Checksum::Crc32,
+		block_size: Some(0x10000),
+		sync_markers: true,
+		..Config::default()
+	});
+	let mut failed: usize = 0;
+	for config in &configs {
+		let mut label: String = format!("{}, {}", config.profile.name(), config.checksum.name());
+		if let Some(block_size) = config.block_size {
+			label.push_str(&format!(", synced blocks of {}", block_size));
+		}
+		for (name, data) in &cases {
+			match round_trip(data, config) {
+				Ok(compressed_size) => println!(
+					"pass: {} ({}), {} -> {}",
+					name,
+					label,
+					data.len(),
+					compressed_size
+				),
+				Err(error) => {
+					println!("FAIL: {} ({}), {}", name, label, error);
+					failed += 1;
+				}
+			}
+		}
+	}
+	let total: usize = configs.len() * cases.len();
+	println!("{} of {} tests passed.", total - failed, total);
+	failed == 0
+}
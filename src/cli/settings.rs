@@ -0,0 +1,132 @@
+/*
+ * srx: The fast Symbol Ranking based compressor.
+ * Copyright (C) 2023-2024  Mai Thanh Minh (a.k.a. thanhminhmr)
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU General Public License as published by the Free Software
+ * Foundation, either  version 3 of the  License,  or (at your option) any later
+ * version.
+ *
+ * This program  is distributed in the hope  that it will be useful, but WITHOUT
+ * ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+ * FOR  A PARTICULAR PURPOSE. See  the  GNU  General  Public   License  for more
+ * details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+ */
+
+use srx::{set_pipe_tuning, AnyError, AnyResult, PipeTuning};
+use std::env::var_os;
+use std::fs::{create_dir_all, read_to_string, write};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+// -----------------------------------------------
+
+// The settings file holds the defaults found by srx tune, one "name = value" per line:
+//   io_buffer_size = 1048576
+//   message_buffer_size = 262144
+//   pipe_depth = 2
+//   threads = 4
+// Lines starting with # are comments, a missing setting keeps the built-in default.
+
+const FILE_NAME: &str = "srx.conf";
+
+#[derive(Default)]
+pub struct Settings {
+	pub tuning: PipeTuning,
+	pub threads: Option<usize>,
+}
+
+static SETTINGS: OnceLock<Settings> = OnceLock::new();
+
+// -----------------------------------------------
+
+// SRX_CONFIG if set, otherwise srx/srx.conf in the configuration directory of the user
+pub fn settings_path() -> Option<PathBuf> {
+	if let Some(path) = var_os("SRX_CONFIG") {
+		return Some(PathBuf::from(path));
+	}
+	let directory: PathBuf = match (var_os("XDG_CONFIG_HOME"), var_os("HOME"), var_os("APPDATA")) {
+		(Some(config), _, _) => PathBuf::from(config),
+		(None, Some(home), _) => Path::new(&home).join(".config"),
+		(None, None, Some(app_data)) => PathBuf::from(app_data),
+		(None, None, None) => return None,
+	};
+	Some(directory.join("srx").join(FILE_NAME))
+}
+
+fn parse(text: &str) -> AnyResult<Settings> {
+	let mut settings: Settings = Settings::default();
+	for line in text.lines().map(str::trim) {
+		if line.is_empty() || line.starts_with('#') {
+			continue;
+		}
+		let (name, value): (&str, usize) = match line.split_once('=') {
+			Some((name, value)) => match value.trim().parse() {
+				Ok(value) => (name.trim(), value),
+				Err(_) => return Err(AnyError::from_string("Invalid setting value!")),
+			},
+			None => return Err(AnyError::from_string("Invalid setting line!")),
+		};
+		match name {
+			"io_buffer_size" => settings.tuning.io_buffer_size = value,
+			"message_buffer_size" => settings.tuning.message_buffer_size = value,
+			"pipe_depth" => settings.tuning.pipe_depth = value,
+			"threads" => settings.threads = Some(value.max(1)),
+			_ => return Err(AnyError::from_string("Unknown setting!")),
+		}
+	}
+	if !settings.tuning.is_valid() {
+		return Err(AnyError::from_string("Unsupported pipe tuning!"));
+	}
+	Ok(settings)
+}
+
+fn load() -> Settings {
+	let path: PathBuf = match settings_path() {
+		Some(path) if path.is_file() => path,
+		_ => return Settings::default(),
+	};
+	match read_to_string(&path)
+		.map_err(AnyError::from)
+		.and_then(|text| parse(&text))
+	{
+		Ok(settings) => settings,
+		Err(error) => {
+			eprintln!("{}: Ignored, {}", path.display(), error);
+			Settings::default()
+		}
+	}
+}
+
+// the settings of this run, read from the file the first time they are needed
+pub fn settings() -> &'static Settings {
+	SETTINGS.get_or_init(load)
+}
+
+// tune the pipes before anything is compressed
+pub fn apply_settings() {
+	// the tuning was checked when read
+	let _checked_ = set_pipe_tuning(settings().tuning);
+}
+
+pub fn save_settings(path: &Path, settings: &Settings) -> AnyResult<()> {
+	if let Some(directory) = path.parent() {
+		create_dir_all(directory)?;
+	}
+	let mut text: String = String::from("# srx defaults, written by srx tune\n");
+	text.push_str(&format!(
+		"io_buffer_size = {}\nmessage_buffer_size = {}\npipe_depth = {}\n",
+		settings.tuning.io_buffer_size,
+		settings.tuning.message_buffer_size,
+		settings.tuning.pipe_depth
+	));
+	if let Some(threads) = settings.threads {
+		text.push_str(&format!("threads = {}\n", threads));
+	}
+	write(path, text)?;
+	Ok(())
+}
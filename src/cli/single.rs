@@ -0,0 +1,188 @@
+/*
+ * srx: The fast Symbol Ranking based compressor.
+ * Copyright (C) 2023-2024  Mai Thanh Minh (a.k.a. thanhminhmr)
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU General Public License as published by the Free Software
+ * Foundation, either  version 3 of the  License,  or (at your option) any later
+ * version.
+ *
+ * This program  is distributed in the hope  that it will be useful, but WITHOUT
+ * ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+ * FOR  A PARTICULAR PURPOSE. See  the  GNU  General  Public   License  for more
+ * details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+ */
+
+use super::options::Options;
+use super::stream::{detect, is_standard, open_input, open_output, Counter, Peeked};
+use srx::{compress, compress_with_stats, decompress, AnyResult, Config, MatchStats};
+use std::fs::{metadata, Metadata};
+use std::io::{Cursor, Read, Write};
+use std::path::Path;
+use std::time::Instant;
+
+// -----------------------------------------------
+
+type Input = Peeked<Counter<Box<dyn Read + Send>>>;
+type Output = Counter<Box<dyn Write + Send>>;
+
+// -----------------------------------------------
+
+// without a direction, compressed input is decompressed and anything else is compressed, the
+// statistics are only there when asked for and compressing
+pub fn run(
+	input_path: &Path,
+	output_path: &Path,
+	direction: Option<bool>,
+	options: &Options,
+) -> AnyResult<(bool, u64, u64, f64, Option<MatchStats>)> {
+	// open file
+	let reader: Counter<Box<dyn Read + Send>> = Counter::new(open_input(input_path)?);
+	let writer: Output = Counter::new(open_output(output_path)?);
+
+	// record the size of a regular file in the header
+	let content_size: Option<u64> = if is_standard(input_path) {
+		None
+	} else {
+		let metadata: Metadata = metadata(input_path)?;
+		metadata.is_file().then_some(metadata.len())
+	};
+	let config: Config = Config {
+		content_size,
+		..options.config.clone()
+	};
+
+	// start the timer
+	let start: Instant = Instant::now();
+
+	// look at the input if needed, the bytes looked at are still read by the codec
+	let (is_compress, reader): (bool, Input) = match direction {
+		Some(is_compress) => (is_compress, Cursor::new(Vec::new()).chain(reader)),
+		None => {
+			let (is_compressed, reader): (bool, Input) = detect(reader)?;
+			(!is_compressed, reader)
+		}
+	};
+
+	// do the compression/decompression
+	let (done_reader, mut done_writer, match_stats): (Input, Output, Option<MatchStats>) =
+		match is_compress {
+			true if options.stats => {
+				let (reader, writer, match_stats): (Input, Output, MatchStats) =
+					compress_with_stats(reader, writer, &config)?;
+				(reader, writer, Some(match_stats))
+			}
+			true => with_no_stats(compress(reader, writer, &config)?),
+			false => with_no_stats(decompress(reader, writer)?),
+		};
+	done_writer.flush()?;
+
+	// stop the timer and calculate the duration in seconds
+	let duration: f64 = start.elapsed().as_millis() as f64 / 1000.0;
+
+	// get the input and output size
+	let input_size: u64 = done_reader.into_inner().1.count();
+	let output_size: u64 = done_writer.count();
+
+	// oke
+	Ok((is_compress, input_size, output_size, duration, match_stats))
+}
+
+fn with_no_stats((reader, writer): (Input, Output)) -> (Input, Output, Option<MatchStats>) {
+	(reader, writer, None)
+}
+
+pub fn report(input_size: u64, output_size: u64, duration: f64, is_compress: bool) -> String {
+	// calculating and report
+	let (percentage, speed) = if is_compress {
+		(
+			output_size as f64 / input_size as f64 * 100.0,
+			input_size as f64 / duration / (1 << 20) as f64,
+		)
+	} else {
+		(
+			input_size as f64 / output_size as f64 * 100.0,
+			output_size as f64 / duration / (1 << 20) as f64,
+		)
+	};
+	format!(
+		"{} -> {} ({:.2}%) in {:.2} seconds ({:.2} MiB/s)",
+		input_size, output_size, percentage, duration, speed
+	)
+}
+
+// a byte as itself when it can be read, or as its value
+fn show_byte(byte: u8) -> String {
+	if byte.is_ascii_graphic() || byte == b' ' {
+		format!("'{}'", byte as char)
+	} else {
+		format!("0x{:02x}", byte)
+	}
+}
+
+fn stats_report(stats: &MatchStats) -> String {
+	let literals: Vec<String> = stats
+		.top_literals(8)
+		.into_iter()
+		.map(|(byte, count)| {
+			format!(
+				"{} {:.2}%",
+				show_byte(byte),
+				count as f64 / stats.no_match as f64 * 100.0
+			)
+		})
+		.collect();
+	format!(
+		"Matched first {:.2}%, second {:.2}%, third {:.2}%, not at all {:.2}% of {} bytes\n\
+		Literals carry {:.2} bits each at order 0, most frequent: {}",
+		stats.ratio(stats.match_first) * 100.0,
+		stats.ratio(stats.match_second) * 100.0,
+		stats.ratio(stats.match_third) * 100.0,
+		stats.ratio(stats.no_match) * 100.0,
+		stats.total(),
+		stats.literal_entropy(),
+		literals.join(", ")
+	)
+}
+
+// -----------------------------------------------
+
+// the output may be the standard output, report on the standard error then
+fn print(output_path: &Path, message: &str) {
+	if is_standard(output_path) {
+		eprintln!("{}", message);
+	} else {
+		println!("{}", message);
+	}
+}
+
+pub fn run_single(options: &Options) -> bool {
+	// run the compression
+	let output_path: &Path = &options.paths[1];
+	match run(
+		&options.paths[0],
+		output_path,
+		(!options.is_auto).then_some(options.is_compress),
+		options,
+	) {
+		Ok((is_compress, input_size, output_size, duration, match_stats)) => {
+			print(
+				output_path,
+				&report(input_size, output_size, duration, is_compress),
+			);
+			if let Some(match_stats) = match_stats {
+				print(output_path, &stats_report(&match_stats));
+			}
+			true
+		}
+		Err(error) => {
+			// something unexpected happened
+			print(output_path, &format!("Error occurred! {}", error));
+			false
+		}
+	}
+}
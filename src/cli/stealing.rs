@@ -0,0 +1,173 @@
+/*
+ * srx: The fast Symbol Ranking based compressor.
+ * Copyright (C) 2023-2024  Mai Thanh Minh (a.k.a. thanhminhmr)
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU General Public License as published by the Free Software
+ * Foundation, either  version 3 of the  License,  or (at your option) any later
+ * version.
+ *
+ * This program  is distributed in the hope  that it will be useful, but WITHOUT
+ * ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+ * FOR  A PARTICULAR PURPOSE. See  the  GNU  General  Public   License  for more
+ * details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+ */
+
+use std::collections::VecDeque;
+use std::sync::{Condvar, Mutex, MutexGuard};
+use std::thread::scope;
+
+// -----------------------------------------------
+
+// A small work-stealing executor. Every worker has its own queue: it takes jobs from the front of
+// it and, once it is empty, steals from the back of the other queues. A running job can push new
+// jobs (e.g. the entries of a directory) to the queue of its worker.
+pub struct Worker<'a, J> {
+	index: usize,
+	queues: &'a [Mutex<VecDeque<J>>],
+	pending: &'a Pending,
+}
+
+// the number of jobs queued or running, idle workers wait for it to change
+struct Pending {
+	count: Mutex<usize>,
+	changed: Condvar,
+}
+
+// a job is done once it has run, even if it panicked, so that the other workers still finish and
+// the panic reaches the caller
+struct Done<'a>(&'a Pending);
+
+impl<'a> Drop for Done<'a> {
+	fn drop(&mut self) {
+		*lock(&self.0.count) -= 1;
+		self.0.changed.notify_all();
+	}
+}
+
+impl<'a, J> Worker<'a, J> {
+	// add a job to the queue of this worker
+	pub fn push(&self, job: J) {
+		// count the job before it can be taken, so the count never drops to zero too early
+		let mut count: MutexGuard<usize> = lock(&self.pending.count);
+		*count += 1;
+		lock(&self.queues[self.index]).push_back(job);
+		drop(count);
+		self.pending.changed.notify_one();
+	}
+
+	fn next(&self) -> Option<J> {
+		if let Some(job) = lock(&self.queues[self.index]).pop_front() {
+			return Some(job);
+		}
+		// steal from the others, starting with the next worker
+		(1..self.queues.len())
+			.map(|offset| (self.index + offset) % self.queues.len())
+			.find_map(|index| lock(&self.queues[index]).pop_back())
+	}
+
+	// the next job, waiting while the running jobs may still push more, none once all are done
+	fn wait_next(&self) -> Option<J> {
+		if let Some(job) = self.next() {
+			return Some(job);
+		}
+		// jobs are pushed with the count locked, none can slip in between looking and waiting
+		let mut count: MutexGuard<usize> = lock(&self.pending.count);
+		loop {
+			if let Some(job) = self.next() {
+				return Some(job);
+			}
+			if *count == 0 {
+				return None;
+			}
+			count = self
+				.pending
+				.changed
+				.wait(count)
+				.unwrap_or_else(|error| error.into_inner());
+		}
+	}
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+	mutex.lock().unwrap_or_else(|error| error.into_inner())
+}
+
+// run all jobs (and the jobs they push) on the given number of threads
+pub fn run_stealing<J: Send, F: Fn(&Worker<J>, J) + Sync>(threads: usize, jobs: Vec<J>, run: F) {
+	let threads: usize = threads.max(1);
+	let pending: Pending = Pending {
+		count: Mutex::new(jobs.len()),
+		changed: Condvar::new(),
+	};
+	let queues: Vec<Mutex<VecDeque<J>>> =
+		(0..threads).map(|_| Mutex::new(VecDeque::new())).collect();
+	for (index, job) in jobs.into_iter().enumerate() {
+		lock(&queues[index % threads]).push_back(job);
+	}
+	scope(|scope| {
+		for index in 0..threads {
+			let worker: Worker<J> = Worker {
+				index,
+				queues: &queues,
+				pending: &pending,
+			};
+			let run: &F = &run;
+			scope.spawn(move || {
+				while let Some(job) = worker.wait_next() {
+					let _done: Done = Done(worker.pending);
+					run(&worker, job);
+				}
+			});
+		}
+	});
+}
+
+// -----------------------------------------------
+
+// A memory budget shared by concurrent jobs. A job asking for more than the whole budget is let
+// through once nothing else is running, so it still makes progress.
+pub struct MemoryBudget {
+	total: usize,
+	available: Mutex<usize>,
+	released: Condvar,
+}
+
+pub struct MemoryLease<'a> {
+	budget: &'a MemoryBudget,
+	size: usize,
+}
+
+impl MemoryBudget {
+	pub fn new(total: usize) -> Self {
+		Self {
+			total,
+			available: Mutex::new(total),
+			released: Condvar::new(),
+		}
+	}
+
+	pub fn acquire(&self, size: usize) -> MemoryLease<'_> {
+		let size: usize = size.min(self.total);
+		let mut available: MutexGuard<usize> = lock(&self.available);
+		while *available < size {
+			available = self
+				.released
+				.wait(available)
+				.unwrap_or_else(|error| error.into_inner());
+		}
+		*available -= size;
+		MemoryLease { budget: self, size }
+	}
+}
+
+impl<'a> Drop for MemoryLease<'a> {
+	fn drop(&mut self) {
+		*lock(&self.budget.available) += self.size;
+		self.budget.released.notify_all();
+	}
+}
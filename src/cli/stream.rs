@@ -0,0 +1,100 @@
+/*
+ * srx: The fast Symbol Ranking based compressor.
+ * Copyright (C) 2023-2024  Mai Thanh Minh (a.k.a. thanhminhmr)
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU General Public License as published by the Free Software
+ * Foundation, either  version 3 of the  License,  or (at your option) any later
+ * version.
+ *
+ * This program  is distributed in the hope  that it will be useful, but WITHOUT
+ * ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+ * FOR  A PARTICULAR PURPOSE. See  the  GNU  General  Public   License  for more
+ * details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+ */
+
+use srx::{is_srx, AnyResult};
+use std::fs::File;
+use std::io::{stdin, stdout, Chain, Cursor, Read, Result, Write};
+use std::path::Path;
+
+// -----------------------------------------------
+
+// a reader with the bytes already taken from it put back in front
+pub type Peeked<R> = Chain<Cursor<Vec<u8>>, R>;
+
+// -----------------------------------------------
+
+// a reader or writer that counts the bytes going through it
+pub struct Counter<T> {
+	inner: T,
+	count: u64,
+}
+
+impl<T> Counter<T> {
+	pub fn new(inner: T) -> Self {
+		Self { inner, count: 0 }
+	}
+
+	pub fn count(&self) -> u64 {
+		self.count
+	}
+
+	pub fn get_ref(&self) -> &T {
+		&self.inner
+	}
+}
+
+impl<T: Read> Read for Counter<T> {
+	fn read(&mut self, buffer: &mut [u8]) -> Result<usize> {
+		let length: usize = self.inner.read(buffer)?;
+		self.count += length as u64;
+		Ok(length)
+	}
+}
+
+impl<T: Write> Write for Counter<T> {
+	fn write(&mut self, buffer: &[u8]) -> Result<usize> {
+		let length: usize = self.inner.write(buffer)?;
+		self.count += length as u64;
+		Ok(length)
+	}
+
+	fn flush(&mut self) -> Result<()> {
+		self.inner.flush()
+	}
+}
+
+// -----------------------------------------------
+
+// "-" stands for the standard input or output
+pub fn is_standard(path: &Path) -> bool {
+	path.to_str() == Some("-")
+}
+
+pub fn open_input(path: &Path) -> AnyResult<Box<dyn Read + Send>> {
+	Ok(if is_standard(path) {
+		Box::new(stdin())
+	} else {
+		Box::new(File::open(path)?)
+	})
+}
+
+pub fn open_output(path: &Path) -> AnyResult<Box<dyn Write + Send>> {
+	Ok(if is_standard(path) {
+		Box::new(stdout())
+	} else {
+		Box::new(File::create(path)?)
+	})
+}
+
+// tell whether the input is compressed from its first bytes, which are put back in front of it
+pub fn detect<R: Read>(mut reader: R) -> AnyResult<(bool, Peeked<R>)> {
+	let mut prefix: Vec<u8> = Vec::new();
+	reader.by_ref().take(3).read_to_end(&mut prefix)?;
+	Ok((is_srx(&prefix), Cursor::new(prefix).chain(reader)))
+}
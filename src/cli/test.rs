@@ -0,0 +1,361 @@
+/*
+ * srx: The fast Symbol Ranking based compressor.
+ * Copyright (C) 2023-2024  Mai Thanh Minh (a.k.a. thanhminhmr)
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU General Public License as published by the Free Software
+ * Foundation, either  version 3 of the  License,  or (at your option) any later
+ * version.
+ *
+ * This program  is distributed in the hope  that it will be useful, but WITHOUT
+ * ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+ * FOR  A PARTICULAR PURPOSE. See  the  GNU  General  Public   License  for more
+ * details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+ */
+
+use super::batch::{memory, run_batch};
+use super::list::describe;
+use super::network::{receive, send};
+use super::options::{parse, Endpoint, Options};
+use super::single::run;
+use super::stealing::{run_stealing, MemoryBudget, MemoryLease, Worker};
+use super::stream::{detect, Peeked};
+use srx::{compress, is_srx, memory_usage, AnyResult, Config};
+use std::env::temp_dir;
+use std::fs::{create_dir_all, read, remove_dir_all, write, File};
+use std::io::{Cursor, Read, Result, Write};
+use std::net::{Ipv4Addr, SocketAddr, TcpListener, TcpStream};
+use std::panic::catch_unwind;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::thread::{sleep, spawn, JoinHandle};
+use std::time::Duration;
+
+// -----------------------------------------------
+
+// a fresh directory for a test, the tests run at the same time
+fn test_dir(name: &str) -> AnyResult<PathBuf> {
+	let path: PathBuf = temp_dir().join(format!("srx-test-{}-{}", std::process::id(), name));
+	let _ = remove_dir_all(&path);
+	create_dir_all(&path)?;
+	Ok(path)
+}
+
+fn args(args: &[&Path]) -> Vec<String> {
+	["srx", "c"]
+		.into_iter()
+		.map(String::from)
+		.chain(args.iter().map(|path| path.display().to_string()))
+		.collect()
+}
+
+fn batch_args(paths: &[&Path]) -> Vec<String> {
+	let mut args: Vec<String> = args(paths);
+	args.insert(2, String::from("--batch"));
+	args
+}
+
+// -----------------------------------------------
+
+#[test]
+fn test_one_path_compresses_next_to_it() -> AnyResult<()> {
+	let dir: PathBuf = test_dir("one")?;
+	let input: PathBuf = dir.join("a.txt");
+	write(&input, b"one file, one path. ".repeat(64))?;
+	let options: Options = parse(&batch_args(&[&input]));
+	assert!(options.is_batch());
+	assert!(run_batch(&options));
+	assert!(is_srx(&read(dir.join("a.txt.srx"))?));
+	assert_eq!(read(&input)?, b"one file, one path. ".repeat(64));
+	remove_dir_all(&dir)?;
+	Ok(())
+}
+
+#[test]
+fn test_two_paths_are_an_input_and_an_output() -> AnyResult<()> {
+	let dir: PathBuf = test_dir("two")?;
+	let (input, output): (PathBuf, PathBuf) = (dir.join("a.txt"), dir.join("b.txt"));
+	write(&input, b"the first of two files. ".repeat(64))?;
+	write(&output, b"the second file. ".repeat(64))?;
+
+	// with --batch both are inputs, each compressed next to itself
+	let options: Options = parse(&batch_args(&[&input, &output]));
+	assert!(options.is_batch());
+	assert!(run_batch(&options));
+	assert_eq!(read(&output)?, b"the second file. ".repeat(64));
+	assert!(is_srx(&read(dir.join("a.txt.srx"))?));
+	assert!(is_srx(&read(dir.join("b.txt.srx"))?));
+
+	// without it they are an input and an output, replaced as it always was
+	let options: Options = parse(&args(&[&input, &output]));
+	assert!(!options.is_batch());
+	run(&input, &output, Some(true), &options)?;
+	assert_eq!(read(&output)?, read(dir.join("a.txt.srx"))?);
+	remove_dir_all(&dir)?;
+	Ok(())
+}
+
+#[test]
+fn test_many_paths_compress_each_file() -> AnyResult<()> {
+	let dir: PathBuf = test_dir("many")?;
+	let inputs: Vec<PathBuf> = ["a.txt", "b.txt", "c.txt"]
+		.iter()
+		.map(|name| dir.join(name))
+		.collect();
+	for input in &inputs {
+		write(input, b"one of many files. ".repeat(64))?;
+	}
+	let paths: Vec<&Path> = inputs.iter().map(PathBuf::as_path).collect();
+	let options: Options = parse(&batch_args(&paths));
+	assert!(options.is_batch());
+	for _ in 0..2 {
+		assert!(run_batch(&options));
+		for input in &inputs {
+			assert_eq!(read(input)?, b"one of many files. ".repeat(64));
+			assert!(is_srx(&read(input.with_extension("txt.srx"))?));
+		}
+	}
+	remove_dir_all(&dir)?;
+	Ok(())
+}
+
+#[test]
+fn test_decompression_memory_from_the_header() -> AnyResult<()> {
+	let dir: PathBuf = test_dir("memory")?;
+	let path: PathBuf = dir.join("a.txt.srx");
+	let config: Config = Config {
+		content_size: Some(0x1000),
+		..Config::default()
+	};
+	compress(
+		&b"small enough for small tables. ".repeat(133)[..0x1000],
+		File::create(&path)?,
+		&config,
+	)?;
+	let options: Options =
+		parse(&["srx", "d", "--batch", &path.display().to_string()].map(String::from));
+	assert_eq!(memory(&path, &options)?, memory_usage(&config));
+	assert!(memory(&path, &options)? < memory_usage(&Config::default()));
+	remove_dir_all(&dir)?;
+	Ok(())
+}
+
+#[test]
+fn test_list_an_empty_file() -> AnyResult<()> {
+	let dir: PathBuf = test_dir("list")?;
+	let path: PathBuf = dir.join("empty.srx");
+	let config: Config = Config {
+		content_size: Some(0),
+		..Config::default()
+	};
+	compress(&[][..], File::create(&path)?, &config)?;
+	let description: String = describe(&path.display().to_string())?;
+	assert!(description.ends_with(&format!(", 0 -> {}", read(&path)?.len())));
+	remove_dir_all(&dir)?;
+	Ok(())
+}
+
+// -----------------------------------------------
+
+#[test]
+fn test_stealing_runs_pushed_jobs() {
+	// each job pushes its two children, the jobs form a tree of 1000 nodes
+	let done: AtomicUsize = AtomicUsize::new(0);
+	run_stealing(4, vec![0_usize], |worker: &Worker<usize>, job: usize| {
+		for child in [job * 2 + 1, job * 2 + 2] {
+			if child < 1000 {
+				worker.push(child);
+			}
+		}
+		done.fetch_add(1, Ordering::SeqCst);
+	});
+	assert_eq!(done.load(Ordering::SeqCst), 1000);
+}
+
+#[test]
+fn test_stealing_passes_a_panic_on() {
+	// the other jobs still run, then the panic reaches the caller instead of hanging the pool
+	let done: AtomicUsize = AtomicUsize::new(0);
+	let result = catch_unwind(|| {
+		run_stealing(4, (0..16).collect(), |_: &Worker<usize>, job: usize| {
+			if job == 5 {
+				panic!("failing job");
+			}
+			done.fetch_add(1, Ordering::SeqCst);
+		})
+	});
+	assert!(result.is_err());
+	assert_eq!(done.load(Ordering::SeqCst), 15);
+}
+
+#[test]
+fn test_memory_budget_bounds_the_jobs() {
+	let budget: MemoryBudget = MemoryBudget::new(100);
+	let (used, peak): (AtomicUsize, AtomicUsize) = (AtomicUsize::new(0), AtomicUsize::new(0));
+	// 30 each fits three at a time, the job of 500 is let through alone
+	let jobs: Vec<usize> = [30; 12].into_iter().chain([500]).collect();
+	run_stealing(8, jobs, |_: &Worker<usize>, size: usize| {
+		let _lease: MemoryLease = budget.acquire(size);
+		let size: usize = size.min(100);
+		peak.fetch_max(
+			used.fetch_add(size, Ordering::SeqCst) + size,
+			Ordering::SeqCst,
+		);
+		sleep(Duration::from_millis(5));
+		used.fetch_sub(size, Ordering::SeqCst);
+	});
+	assert!(peak.load(Ordering::SeqCst) <= 100);
+	assert_eq!(used.load(Ordering::SeqCst), 0);
+}
+
+// -----------------------------------------------
+
+fn words(line: &str) -> Vec<String> {
+	line.split_whitespace().map(String::from).collect()
+}
+
+// the direction and whether it was left to the input
+fn direction(line: &str) -> (bool, bool) {
+	let options: Options = parse(&words(line));
+	(options.is_compress, options.is_auto)
+}
+
+#[test]
+fn test_parse_direction() {
+	// a command decides, then the last of -z/-d, then the input
+	assert_eq!(direction("srx c a b"), (true, false));
+	assert_eq!(direction("srx d a b"), (false, false));
+	assert_eq!(direction("srx -z a b"), (true, false));
+	assert_eq!(direction("srx -d a b"), (false, false));
+	assert_eq!(direction("srx -z -d a b"), (false, false));
+	assert_eq!(direction("srx --decompress a b --compress"), (true, false));
+	assert_eq!(direction("srx a.txt b"), (true, true));
+	assert_eq!(direction("srx - -"), (true, true));
+}
+
+#[test]
+fn test_parse_sizes() {
+	let options: Options = parse(&words("srx c -b 3 -m 5 a b"));
+	assert_eq!(options.config.block_size, Some(3 << 20));
+	assert_eq!(options.memory_budget, 5 << 20);
+	// a block is at least a MiB
+	let options: Options = parse(&words("srx c -b 0 a b"));
+	assert_eq!(options.config.block_size, Some(1 << 20));
+}
+
+#[test]
+fn test_detect() -> AnyResult<()> {
+	let (_, compressed): (Cursor<&[u8]>, Vec<u8>) = compress(
+		Cursor::new(b"a compressed input. ".as_slice()),
+		Vec::new(),
+		&Config::default(),
+	)?;
+	for (input, is_compressed) in [
+		(compressed.as_slice(), true),
+		(b"some plain text".as_slice(), false),
+		(b"sR".as_slice(), false),
+		(b"".as_slice(), false),
+	] {
+		let (found, mut reader): (bool, Peeked<&[u8]>) = detect(input)?;
+		assert_eq!(found, is_compressed);
+		// the bytes looked at are read again
+		let mut read_back: Vec<u8> = Vec::new();
+		reader.read_to_end(&mut read_back)?;
+		assert_eq!(read_back, input);
+	}
+	Ok(())
+}
+
+// -----------------------------------------------
+
+// an input that only goes on when told to, like a pipe from a program that pauses
+struct ChannelReader(Receiver<Vec<u8>>);
+
+impl Read for ChannelReader {
+	fn read(&mut self, buffer: &mut [u8]) -> Result<usize> {
+		match self.0.recv() {
+			Ok(chunk) => {
+				buffer[..chunk.len()].copy_from_slice(&chunk);
+				Ok(chunk.len())
+			}
+			Err(_) => Ok(0),
+		}
+	}
+}
+
+// an output telling what is written to it as soon as it is
+struct ChannelWriter(Sender<Vec<u8>>);
+
+impl Write for ChannelWriter {
+	fn write(&mut self, buffer: &[u8]) -> Result<usize> {
+		let _ = self.0.send(buffer.to_vec());
+		Ok(buffer.len())
+	}
+
+	fn flush(&mut self) -> Result<()> {
+		Ok(())
+	}
+}
+
+#[test]
+fn test_network_round_trip() -> AnyResult<()> {
+	let listener: TcpListener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0))?;
+	let address: SocketAddr = listener.local_addr()?;
+	let (output, received): (Sender<Vec<u8>>, Receiver<Vec<u8>>) = channel();
+	let receiver: JoinHandle<AnyResult<(u64, u64)>> = spawn(move || {
+		let (stream, _) = listener.accept()?;
+		receive(stream, ChannelWriter(output))
+	});
+	let (input, to_send): (Sender<Vec<u8>>, Receiver<Vec<u8>>) = channel();
+	let stream: TcpStream = TcpStream::connect(address)?;
+	let sender: JoinHandle<AnyResult<(u64, u64)>> =
+		spawn(move || send(ChannelReader(to_send), stream, &Config::default()));
+
+	// each line gets to the other side while the input waits, long before a block is full
+	let mut data: Vec<u8> = Vec::new();
+	let mut output: Vec<u8> = Vec::new();
+	for line in 0..3 {
+		let line: Vec<u8> = format!("line {} of a slow input\n", line).into_bytes();
+		data.extend_from_slice(&line);
+		input.send(line)?;
+		while output.len() < data.len() {
+			output.extend(received.recv_timeout(Duration::from_secs(10))?);
+		}
+		assert_eq!(output, data);
+	}
+	drop(input);
+	let (input_size, sent_size): (u64, u64) = sender.join().unwrap()?;
+	let (received_size, output_size): (u64, u64) = receiver.join().unwrap()?;
+	assert_eq!(
+		(input_size, output_size),
+		(data.len() as u64, data.len() as u64)
+	);
+	assert_eq!(sent_size, received_size);
+	assert!(received.try_iter().all(|chunk| chunk.is_empty()));
+	Ok(())
+}
+
+#[test]
+fn test_parse_endpoints() {
+	let listen = |line: &str| match parse(&words(line)).endpoint {
+		Some(Endpoint::Listen(address)) => Some(address),
+		_ => None,
+	};
+	assert_eq!(
+		listen("srx d --listen 9000 a"),
+		Some(SocketAddr::from((Ipv4Addr::LOCALHOST, 9000)))
+	);
+	assert_eq!(
+		listen("srx d --bind 0.0.0.0 --listen 9000 a"),
+		Some(SocketAddr::from((Ipv4Addr::UNSPECIFIED, 9000)))
+	);
+	assert!(matches!(
+		parse(&words("srx c --connect host:9000 a")).endpoint,
+		Some(Endpoint::Connect(address)) if address == "host:9000"
+	));
+}
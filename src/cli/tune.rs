@@ -0,0 +1,191 @@
+/*
+ * srx: The fast Symbol Ranking based compressor.
+ * Copyright (C) 2023-2024  Mai Thanh Minh (a.k.a. thanhminhmr)
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU General Public License as published by the Free Software
+ * Foundation, either  version 3 of the  License,  or (at your option) any later
+ * version.
+ *
+ * This program  is distributed in the hope  that it will be useful, but WITHOUT
+ * ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+ * FOR  A PARTICULAR PURPOSE. See  the  GNU  General  Public   License  for more
+ * details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+ */
+
+use super::selftest::{random_data, TEXT_CORPUS};
+use super::settings::{save_settings, settings_path, Settings};
+use srx::{compress, decompress, set_pipe_tuning, AnyError, AnyResult, Config, PipeTuning};
+use std::io::Cursor;
+use std::mem::size_of;
+use std::path::PathBuf;
+use std::thread::{available_parallelism, scope, ScopedJoinHandle};
+use std::time::Instant;
+
+// -----------------------------------------------
+
+const SAMPLE_SIZE: usize = 0x400000;
+// every candidate is timed this many times, the fastest run counts
+const RUNS: usize = 2;
+const PIPE_DEPTHS: [usize; 3] = [1, 2, 4];
+// more threads are only worth it if they get this much more done
+const THREAD_GAIN: f64 = 1.05;
+
+// -----------------------------------------------
+
+// lines of text picked at random with a bit of incompressible data in between, so that neither the
+// model nor the pipes see an unusually easy input
+fn sample() -> Vec<u8> {
+	let lines: Vec<&str> = TEXT_CORPUS.lines().collect();
+	let picks: Vec<u8> = random_data(SAMPLE_SIZE / 16, 0x9E3779B97F4A7C15);
+	let mut data: Vec<u8> = Vec::with_capacity(SAMPLE_SIZE + 0x1000);
+	for pick in picks.chunks_exact(2).cycle() {
+		if data.len() >= SAMPLE_SIZE {
+			break;
+		}
+		if pick[0] < 16 {
+			data.extend_from_slice(&random_data(0x100, pick[1] as u64 + 1));
+		} else {
+			let index: usize = (pick[0] as usize) << 8 | pick[1] as usize;
+			data.extend_from_slice(lines[index % lines.len()].as_bytes());
+			data.push(b'\n');
+		}
+	}
+	data.truncate(SAMPLE_SIZE);
+	data
+}
+
+fn round_trip(data: &[u8]) -> AnyResult<()> {
+	let config: Config = Config {
+		content_size: Some(data.len() as u64),
+		..Config::default()
+	};
+	let (_, compressed): (Cursor<&[u8]>, Vec<u8>) =
+		compress(Cursor::new(data), Vec::new(), &config)?;
+	let (_, decompressed): (Cursor<&[u8]>, Vec<u8>) = decompress(
+		Cursor::new(compressed.as_slice()),
+		Vec::with_capacity(data.len()),
+	)?;
+	if decompressed != data {
+		return Err(AnyError::from_string(
+			"Decompressed data does not match the original!",
+		));
+	}
+	Ok(())
+}
+
+// round trips of the sample per second, with that many running at the same time
+fn speed(data: &[u8], threads: usize) -> AnyResult<f64> {
+	let mut best: f64 = f64::MAX;
+	for _ in 0..RUNS {
+		let start: Instant = Instant::now();
+		scope(|scope| -> AnyResult<()> {
+			let workers: Vec<ScopedJoinHandle<AnyResult<()>>> = (0..threads)
+				.map(|_| scope.spawn(|| round_trip(data)))
+				.collect();
+			for worker in workers {
+				match worker.join() {
+					Ok(result) => result?,
+					Err(error) => return Err(AnyError::from_box(error)),
+				}
+			}
+			Ok(())
+		})?;
+		best = best.min(start.elapsed().as_secs_f64());
+	}
+	Ok(threads as f64 / best)
+}
+
+fn mib_per_second(speed: f64) -> f64 {
+	speed * SAMPLE_SIZE as f64 / (1 << 20) as f64
+}
+
+fn run(path: PathBuf) -> AnyResult<()> {
+	let data: Vec<u8> = sample();
+	println!(
+		"Timing round trips of {} bytes, this takes a while.",
+		data.len()
+	);
+
+	// the pipes first, one round trip at a time
+	let mut best: (PipeTuning, f64) = (PipeTuning::DEFAULT, 0.0);
+	for io_buffer_size in PipeTuning::IO_BUFFER_SIZES {
+		for message_buffer_size in PipeTuning::MESSAGE_BUFFER_SIZES {
+			for pipe_depth in PIPE_DEPTHS {
+				let tuning: PipeTuning = PipeTuning {
+					io_buffer_size,
+					message_buffer_size,
+					pipe_depth,
+				};
+				set_pipe_tuning(tuning)?;
+				let speed: f64 = speed(&data, 1)?;
+				println!(
+					"io buffers of {} KiB, message buffers of {} KiB, depth {}: {:.2} MiB/s",
+					io_buffer_size >> 10,
+					(message_buffer_size * size_of::<u32>()) >> 10,
+					pipe_depth,
+					mib_per_second(speed)
+				);
+				if speed > best.1 {
+					best = (tuning, speed);
+				}
+			}
+		}
+	}
+	let tuning: PipeTuning = best.0;
+	set_pipe_tuning(tuning)?;
+
+	// then the number of files processed at the same time, doubling up to the number of cores
+	let cores: usize = available_parallelism().map_or(1, |cores| cores.get());
+	let mut candidates: Vec<usize> = (0..usize::BITS)
+		.map(|shift| 1 << shift)
+		.take_while(|threads| *threads < cores)
+		.collect();
+	candidates.push(cores);
+	let mut best: (usize, f64) = (1, 0.0);
+	for threads in candidates {
+		let speed: f64 = speed(&data, threads)?;
+		println!("{} threads: {:.2} MiB/s", threads, mib_per_second(speed));
+		if speed > best.1 * THREAD_GAIN {
+			best = (threads, speed);
+		}
+	}
+
+	let settings: Settings = Settings {
+		tuning,
+		threads: Some(best.0),
+	};
+	save_settings(&path, &settings)?;
+	println!(
+		"Picked io buffers of {} KiB, message buffers of {} KiB, depth {} and {} threads, saved to {}",
+		tuning.io_buffer_size >> 10,
+		(tuning.message_buffer_size * size_of::<u32>()) >> 10,
+		tuning.pipe_depth,
+		best.0,
+		path.display()
+	);
+	Ok(())
+}
+
+// -----------------------------------------------
+
+pub fn run_tune() -> bool {
+	let path: PathBuf = match settings_path() {
+		Some(path) => path,
+		None => {
+			println!("Error occurred! No place for the settings file, set SRX_CONFIG!");
+			return false;
+		}
+	};
+	match run(path) {
+		Ok(()) => true,
+		Err(error) => {
+			println!("Error occurred! {}", error);
+			false
+		}
+	}
+}
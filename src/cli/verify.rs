@@ -0,0 +1,59 @@
+/*
+ * srx: The fast Symbol Ranking based compressor.
+ * Copyright (C) 2023-2024  Mai Thanh Minh (a.k.a. thanhminhmr)
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU General Public License as published by the Free Software
+ * Foundation, either  version 3 of the  License,  or (at your option) any later
+ * version.
+ *
+ * This program  is distributed in the hope  that it will be useful, but WITHOUT
+ * ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+ * FOR  A PARTICULAR PURPOSE. See  the  GNU  General  Public   License  for more
+ * details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+ */
+
+use srx::{verify, AnyResult, Checksum, VerifyReport};
+use std::fs::File;
+use std::time::Instant;
+
+// -----------------------------------------------
+
+fn describe(path: &str) -> AnyResult<String> {
+	let start: Instant = Instant::now();
+	let report: VerifyReport = verify(File::open(path)?)?;
+	let duration: f64 = start.elapsed().as_secs_f64();
+	// decoding to the end is all that can be checked without a checksum
+	let checked: &str = match report.config.checksum {
+		Checksum::None => "decoded without a checksum",
+		checksum => checksum.name(),
+	};
+	Ok(format!(
+		"ok ({}), {} -> {} in {:.2} seconds ({:.2} MiB/s)",
+		checked,
+		report.compressed_size,
+		report.original_size,
+		duration,
+		report.original_size as f64 / duration / (1 << 20) as f64
+	))
+}
+
+// -----------------------------------------------
+
+pub fn run_verify(paths: &[String]) -> bool {
+	let mut is_success: bool = true;
+	for path in paths {
+		match describe(path) {
+			Ok(description) => println!("{}: {}", path, description),
+			Err(error) => {
+				println!("{}: Error occurred! {}", path, error);
+				is_success = false;
+			}
+		}
+	}
+	is_success
+}
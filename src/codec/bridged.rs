@@ -18,18 +18,56 @@
  */
 
 use crate::basic::Byte;
-use crate::primary_context::{PrimaryContext, PrimaryContextInfo};
-use crate::secondary_context::SecondaryContext;
+use crate::config::{Config, ContextSize};
+use crate::primary_context::{ByteHistory, PrimaryContext, PrimaryContextInfo};
+use crate::secondary_context::{BitState, SecondaryContext};
+use std::mem::size_of;
 
 // -----------------------------------------------
 
-pub const PRIMARY_CONTEXT_SIZE: usize = 1 << 24;
-pub const SECONDARY_CONTEXT_SIZE: usize = 0x4000 * 256 + (1024 + 32) * 768;
+pub type BridgedPrimaryContext = PrimaryContext;
+pub type BridgedSecondaryContext = SecondaryContext;
+
+// the memory taken by the primary and the secondary context
+pub fn context_memory_usage(config: &Config) -> usize {
+	let layout: ContextLayout = ContextLayout::new(config.resolved_context_size());
+	layout.primary_size() * size_of::<ByteHistory>()
+		+ layout.secondary_size() * size_of::<BitState>()
+}
 
 // -----------------------------------------------
 
-pub type BridgedPrimaryContext = PrimaryContext<PRIMARY_CONTEXT_SIZE>;
-pub type BridgedSecondaryContext = SecondaryContext<SECONDARY_CONTEXT_SIZE>;
+// The secondary context starts with the literal contexts (256 bits each), followed by the bit
+// contexts of the first 4 match counts after each previous byte, and those of the longer matches
+// (1024 + 32 blocks of 768 bits)
+pub struct ContextLayout {
+	primary_size: usize,
+	literal_mask: usize,
+	bit_context_base: usize,
+}
+
+impl ContextLayout {
+	pub fn new(context_size: ContextSize) -> Self {
+		debug_assert!(context_size.is_valid());
+		Self {
+			primary_size: 1 << context_size.primary_bits,
+			literal_mask: (1 << context_size.literal_bits) - 1,
+			bit_context_base: 256 << context_size.literal_bits,
+		}
+	}
+
+	pub fn primary_size(&self) -> usize {
+		self.primary_size
+	}
+
+	pub fn secondary_size(&self) -> usize {
+		self.bit_context_base + (1024 + 32) * 768
+	}
+}
+
+// the largest context index is below 2^23, it fits in any usize and in the 30 bits a message of the
+// encoder gives it
+const _: () = assert!((256 << ContextSize::MAX.literal_bits) + (1024 + 32) * 768 < 1 << 30);
 
 // -----------------------------------------------
 
@@ -40,10 +78,10 @@ pub struct BridgedContextInfo {
 }
 
 impl BridgedContextInfo {
-	pub fn new(primary_context_info: PrimaryContextInfo) -> Self {
+	pub fn new(primary_context_info: PrimaryContextInfo, layout: &ContextLayout) -> Self {
 		let match_count: usize = primary_context_info.match_count();
 		Self {
-			bit_context: 0x4000 * 256
+			bit_context: layout.bit_context_base
 				+ if match_count < 4 {
 					(usize::from(primary_context_info.previous_byte()) << 2) | match_count
 				} else {
@@ -53,7 +91,7 @@ impl BridgedContextInfo {
 						31
 					}
 				} * 768,
-			literal_context: (primary_context_info.hash_value() & 0x3FFF) * 256,
+			literal_context: (primary_context_info.hash_value() & layout.literal_mask) * 256,
 			primary_context_info,
 		}
 	}
@@ -17,9 +17,16 @@
  *
  */
 
-use super::bridged::{BridgedContextInfo, BridgedPrimaryContext, BridgedSecondaryContext};
+use super::bridged::{
+	BridgedContextInfo, BridgedPrimaryContext, BridgedSecondaryContext, ContextLayout,
+};
 use super::shared::{run_file_reader, run_file_writer, thread_join};
-use crate::basic::{pipe, AnyResult, Bit, BufferedInputPipe, BufferedOutputPipe, Byte, Closable};
+use crate::basic::{
+	enter_span, pipe, pipe_tuning, pipeline_span, AnyError, AnyResult, Bit, BufferedInputPipe,
+	BufferedOutputPipe, Byte, Closable, PipelineSpan,
+};
+use crate::checksum::{Checksum, DigestWriter};
+use crate::config::Config;
 use crate::primary_context::ByteMatched;
 use crate::secondary_context::{BitDecoder, StateInfo};
 use std::io::{Read, Write};
@@ -29,10 +36,12 @@ use std::thread::{scope, ScopedJoinHandle};
 
 // currently there is no way to split the decoder into two separate passes like the encoder
 struct CombinedContextDecoder<const IO_BUFFER_SIZE: usize> {
+	layout: ContextLayout,
 	primary_context: BridgedPrimaryContext,
 	secondary_context: BridgedSecondaryContext,
 	decoder: BitDecoder<IO_BUFFER_SIZE>,
 	output: BufferedOutputPipe<u8, IO_BUFFER_SIZE>,
+	checksum: Checksum,
 }
 
 impl<const IO_BUFFER_SIZE: usize> CombinedContextDecoder<IO_BUFFER_SIZE> {
@@ -47,22 +56,36 @@ impl<const IO_BUFFER_SIZE: usize> CombinedContextDecoder<IO_BUFFER_SIZE> {
 
 	fn byte(&mut self, context_index: usize) -> AnyResult<Byte> {
 		let mut high: usize = 1;
-		high += high + usize::from(self.bit(context_index + high)?);
-		high += high + usize::from(self.bit(context_index + high)?);
-		high += high + usize::from(self.bit(context_index + high)?);
-		high += high + usize::from(self.bit(context_index + high)?);
+		high = (high << 1) | usize::from(self.bit(context_index + high)?);
+		high = (high << 1) | usize::from(self.bit(context_index + high)?);
+		high = (high << 1) | usize::from(self.bit(context_index + high)?);
+		high = (high << 1) | usize::from(self.bit(context_index + high)?);
 		let low_context: usize = context_index + 15 * (high - 15);
 		let mut low: usize = 1;
-		low += low + usize::from(self.bit(low_context + low)?);
-		low += low + usize::from(self.bit(low_context + low)?);
-		low += low + usize::from(self.bit(low_context + low)?);
-		low += low + usize::from(self.bit(low_context + low)?);
+		low = (low << 1) | usize::from(self.bit(low_context + low)?);
+		low = (low << 1) | usize::from(self.bit(low_context + low)?);
+		low = (low << 1) | usize::from(self.bit(low_context + low)?);
+		low = (low << 1) | usize::from(self.bit(low_context + low)?);
 		Ok(Byte::from(((high - 16) << 4) | (low - 16)))
 	}
 
-	fn decode(mut self) -> AnyResult<()> {
+	// the checksum digest follows the end of stream, coded without a model
+	fn digest(&mut self) -> AnyResult<Vec<u8>> {
+		let mut digest: Vec<u8> = Vec::with_capacity(self.checksum.digest_length());
+		for _ in 0..self.checksum.digest_length() {
+			let mut byte: u8 = 0;
+			for _ in 0..8 {
+				byte = (byte << 1) | u8::from(self.decoder.bit(0x80000000)?);
+			}
+			digest.push(byte);
+		}
+		Ok(digest)
+	}
+
+	fn decode(mut self) -> AnyResult<Vec<u8>> {
 		loop {
-			let info: BridgedContextInfo = BridgedContextInfo::new(self.primary_context.get_info());
+			let info: BridgedContextInfo =
+				BridgedContextInfo::new(self.primary_context.get_info(), &self.layout);
 			let (next_byte, matched): (Byte, ByteMatched) = match self.bit(info.first_context())? {
 				// match first
 				Bit::Zero => (info.first_byte(), ByteMatched::MatchFirst),
@@ -73,9 +96,10 @@ impl<const IO_BUFFER_SIZE: usize> CombinedContextDecoder<IO_BUFFER_SIZE> {
 						let next_byte: Byte = self.byte(info.literal_context())?;
 						if next_byte == info.first_byte() {
 							// eof
+							let digest: Vec<u8> = self.digest()?;
 							self.decoder.close()?;
 							self.output.close()?;
-							return Ok(());
+							return Ok(digest);
 						}
 						(next_byte, ByteMatched::NoMatch)
 					}
@@ -99,12 +123,19 @@ impl<const IO_BUFFER_SIZE: usize> CombinedContextDecoder<IO_BUFFER_SIZE> {
 fn run_combined_context_decoder<const IO_BUFFER_SIZE: usize>(
 	input: BufferedInputPipe<u8, IO_BUFFER_SIZE>,
 	output: BufferedOutputPipe<u8, IO_BUFFER_SIZE>,
-) -> AnyResult<()> {
+	config: &Config,
+) -> AnyResult<Vec<u8>> {
+	let layout: ContextLayout = ContextLayout::new(config.resolved_context_size());
 	let decoder: CombinedContextDecoder<IO_BUFFER_SIZE> = CombinedContextDecoder {
-		primary_context: BridgedPrimaryContext::new(),
-		secondary_context: BridgedSecondaryContext::new(),
+		primary_context: BridgedPrimaryContext::new(layout.primary_size()),
+		secondary_context: BridgedSecondaryContext::with_profile(
+			layout.secondary_size(),
+			config.profile,
+		),
+		layout,
 		decoder: BitDecoder::new(input),
 		output,
+		checksum: config.checksum,
 	};
 	decoder.decode()
 }
@@ -114,36 +145,72 @@ fn run_combined_context_decoder<const IO_BUFFER_SIZE: usize>(
 pub fn decode<R: Read + Send, W: Write + Send, const IO_BUFFER_SIZE: usize>(
 	reader: R,
 	writer: W,
+	config: &Config,
 ) -> AnyResult<(R, W)> {
+	// the pipes are as deep as the process is tuned for
+	decode_piped::<R, W, IO_BUFFER_SIZE>(reader, writer, config, pipe_tuning().pipe_depth)
+}
+
+// Decode like decode() does, with pipes of the given depth
+pub fn decode_piped<R: Read + Send, W: Write + Send, const IO_BUFFER_SIZE: usize>(
+	reader: R,
+	writer: W,
+	config: &Config,
+	depth: usize,
+) -> AnyResult<(R, W)> {
+	// the spans of the threads are children of this one
+	let span: PipelineSpan = pipeline_span!(
+		"decode",
+		profile = config.profile.name(),
+		checksum = config.checksum.name()
+	);
+	enter_span!(span);
 	scope(|scope| {
 		// create pipe between file reader thread and decoder thread
 		let (reader_output_pipe, reader_input_pipe): (
 			BufferedOutputPipe<u8, IO_BUFFER_SIZE>,
 			BufferedInputPipe<u8, IO_BUFFER_SIZE>,
-		) = pipe::<u8, IO_BUFFER_SIZE>();
+		) = pipe::<u8, IO_BUFFER_SIZE>(depth);
 
 		// create pipe between decoder thread and file writer thread
 		let (writer_output_pipe, writer_input_pipe): (
 			BufferedOutputPipe<u8, IO_BUFFER_SIZE>,
 			BufferedInputPipe<u8, IO_BUFFER_SIZE>,
-		) = pipe::<u8, IO_BUFFER_SIZE>();
+		) = pipe::<u8, IO_BUFFER_SIZE>(depth);
 
 		// create file reader thread
-		let file_reader: ScopedJoinHandle<AnyResult<R>> =
-			scope.spawn(|| run_file_reader(reader, reader_output_pipe));
+		let reader_span: PipelineSpan = pipeline_span!("reader");
+		let file_reader: ScopedJoinHandle<AnyResult<R>> = scope.spawn(|| {
+			enter_span!(reader_span);
+			run_file_reader(reader, reader_output_pipe)
+		});
 
 		// create decoder thread
-		let combined_context_decoder: ScopedJoinHandle<AnyResult<()>> =
-			scope.spawn(|| run_combined_context_decoder(reader_input_pipe, writer_output_pipe));
+		let decoder_span: PipelineSpan = pipeline_span!("combined_context");
+		let combined_context_decoder: ScopedJoinHandle<AnyResult<Vec<u8>>> = scope.spawn(|| {
+			enter_span!(decoder_span);
+			run_combined_context_decoder(reader_input_pipe, writer_output_pipe, config)
+		});
 
 		// create file writer thread
-		let file_writer: ScopedJoinHandle<AnyResult<W>> =
-			scope.spawn(|| run_file_writer(writer_input_pipe, writer));
+		let writer_span: PipelineSpan = pipeline_span!("writer");
+		let file_writer: ScopedJoinHandle<AnyResult<(W, Vec<u8>)>> = scope.spawn(|| {
+			enter_span!(writer_span);
+			let digest_writer: DigestWriter<W> = DigestWriter::new(writer, config.checksum);
+			Ok(run_file_writer(writer_input_pipe, digest_writer)?.finish())
+		});
 
 		// join all thread
 		let returned_reader: R = thread_join(file_reader)?;
-		thread_join(combined_context_decoder)?;
-		let returned_writer: W = thread_join(file_writer)?;
+		let stored_digest: Vec<u8> = thread_join(combined_context_decoder)?;
+		let (returned_writer, digest): (W, Vec<u8>) = thread_join(file_writer)?;
+
+		// check the decompressed data against the stored checksum
+		if digest != stored_digest {
+			return Err(AnyError::from_string(
+				"Checksum mismatch, the data is corrupted!",
+			));
+		}
 
 		// give back the file handlers
 		Ok((returned_reader, returned_writer))
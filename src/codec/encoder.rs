@@ -17,12 +17,22 @@
  *
  */
 
-use super::bridged::{BridgedContextInfo, BridgedPrimaryContext, BridgedSecondaryContext};
+use super::bridged::{
+	BridgedContextInfo, BridgedPrimaryContext, BridgedSecondaryContext, ContextLayout,
+};
 use super::shared::{run_file_reader, run_file_writer, thread_join};
-use crate::basic::{pipe, AnyResult, Bit, BufferedInputPipe, BufferedOutputPipe, Byte, Closable};
+use super::stats::MatchCounter;
+use super::tracer::Tracer;
+use crate::basic::{
+	enter_span, pipe, pipe_tuning, pipeline_span, AnyResult, Bit, BufferedInputPipe,
+	BufferedOutputPipe, Byte, Closable, PipelineSpan,
+};
+use crate::checksum::DigestReader;
+use crate::config::Config;
 use crate::primary_context::ByteMatched;
 use crate::secondary_context::{BitEncoder, StateInfo};
 use std::io::{Read, Write};
+use std::sync::mpsc::{channel, Receiver, Sender};
 use std::thread::{scope, ScopedJoinHandle};
 
 // -----------------------------------------------
@@ -58,13 +68,20 @@ impl PackedMessage {
 
 // -----------------------------------------------
 
-fn run_primary_context_encoder<const IO_BUFFER_SIZE: usize, const MESSAGE_BUFFER_SIZE: usize>(
+fn run_primary_context_encoder<
+	C: MatchCounter,
+	const IO_BUFFER_SIZE: usize,
+	const MESSAGE_BUFFER_SIZE: usize,
+>(
 	mut input: BufferedInputPipe<u8, IO_BUFFER_SIZE>,
 	mut output: BufferedOutputPipe<PackedMessage, MESSAGE_BUFFER_SIZE>,
-) -> AnyResult<()> {
-	let mut context: BridgedPrimaryContext = BridgedPrimaryContext::new();
+	config: &Config,
+	mut counter: C,
+) -> AnyResult<C> {
+	let layout: ContextLayout = ContextLayout::new(config.resolved_context_size());
+	let mut context: BridgedPrimaryContext = BridgedPrimaryContext::new(layout.primary_size());
 	loop {
-		let info: BridgedContextInfo = BridgedContextInfo::new(context.get_info());
+		let info: BridgedContextInfo = BridgedContextInfo::new(context.get_info(), &layout);
 		match input.produce()? {
 			None => {
 				output.output(PackedMessage::bit(info.first_context(), Bit::One))?;
@@ -75,50 +92,62 @@ fn run_primary_context_encoder<const IO_BUFFER_SIZE: usize, const MESSAGE_BUFFER
 				))?;
 				input.close()?;
 				output.close()?;
-				return Ok(());
+				return Ok(counter);
 			}
-			Some(current_byte) => match context.matching(Byte::from(current_byte)) {
-				ByteMatched::MatchFirst => {
-					output.output(PackedMessage::bit(info.first_context(), Bit::Zero))?;
-				}
-				ByteMatched::NoMatch => {
-					output.output(PackedMessage::bit(info.first_context(), Bit::One))?;
-					output.output(PackedMessage::bit(info.second_context(), Bit::Zero))?;
-					output.output(PackedMessage::byte(
-						info.literal_context(),
-						Byte::from(current_byte),
-					))?;
-				}
-				ByteMatched::MatchSecond => {
-					output.output(PackedMessage::bit(info.first_context(), Bit::One))?;
-					output.output(PackedMessage::bit(info.second_context(), Bit::One))?;
-					output.output(PackedMessage::bit(info.third_context(), Bit::Zero))?;
+			Some(current_byte) => {
+				let matched: ByteMatched = context.matching(Byte::from(current_byte));
+				counter.matched(current_byte, matched);
+				match matched {
+					ByteMatched::MatchFirst => {
+						output.output(PackedMessage::bit(info.first_context(), Bit::Zero))?;
+					}
+					ByteMatched::NoMatch => {
+						output.output(PackedMessage::bit(info.first_context(), Bit::One))?;
+						output.output(PackedMessage::bit(info.second_context(), Bit::Zero))?;
+						output.output(PackedMessage::byte(
+							info.literal_context(),
+							Byte::from(current_byte),
+						))?;
+					}
+					ByteMatched::MatchSecond => {
+						output.output(PackedMessage::bit(info.first_context(), Bit::One))?;
+						output.output(PackedMessage::bit(info.second_context(), Bit::One))?;
+						output.output(PackedMessage::bit(info.third_context(), Bit::Zero))?;
+					}
+					ByteMatched::MatchThird => {
+						output.output(PackedMessage::bit(info.first_context(), Bit::One))?;
+						output.output(PackedMessage::bit(info.second_context(), Bit::One))?;
+						output.output(PackedMessage::bit(info.third_context(), Bit::One))?;
+					}
 				}
-				ByteMatched::MatchThird => {
-					output.output(PackedMessage::bit(info.first_context(), Bit::One))?;
-					output.output(PackedMessage::bit(info.second_context(), Bit::One))?;
-					output.output(PackedMessage::bit(info.third_context(), Bit::One))?;
-				}
-			},
+			}
 		}
 	}
 }
 
 // -----------------------------------------------
 
-struct SecondaryContextEncoder<const IO_BUFFER_SIZE: usize, const MESSAGE_BUFFER_SIZE: usize> {
+struct SecondaryContextEncoder<
+	T: Tracer,
+	const IO_BUFFER_SIZE: usize,
+	const MESSAGE_BUFFER_SIZE: usize,
+> {
 	context: BridgedSecondaryContext,
 	input: BufferedInputPipe<PackedMessage, MESSAGE_BUFFER_SIZE>,
 	encoder: BitEncoder<IO_BUFFER_SIZE>,
+	tracer: T,
+	digest: Receiver<Vec<u8>>,
 }
 
-impl<const IO_BUFFER_SIZE: usize, const MESSAGE_BUFFER_SIZE: usize>
-	SecondaryContextEncoder<IO_BUFFER_SIZE, MESSAGE_BUFFER_SIZE>
+impl<T: Tracer, const IO_BUFFER_SIZE: usize, const MESSAGE_BUFFER_SIZE: usize>
+	SecondaryContextEncoder<T, IO_BUFFER_SIZE, MESSAGE_BUFFER_SIZE>
 {
 	#[inline(always)]
 	fn bit(&mut self, context_index: usize, bit: Bit) -> AnyResult<()> {
 		let current_state: StateInfo = self.context.get_info(context_index);
 		self.context.update(current_state, context_index, bit);
+		self.tracer
+			.bit(context_index, current_state.prediction(), bit)?;
 		self.encoder.bit(current_state.prediction(), bit)
 	}
 
@@ -140,13 +169,24 @@ impl<const IO_BUFFER_SIZE: usize, const MESSAGE_BUFFER_SIZE: usize>
 		Ok(())
 	}
 
-	fn encode(mut self) -> AnyResult<()> {
+	// the checksum digest follows the end of stream, coded without a model
+	fn digest(&mut self) -> AnyResult<()> {
+		for byte in self.digest.recv()? {
+			for shift in (0..8).rev() {
+				self.encoder.bit(0x80000000, Bit::from(byte >> shift & 1))?;
+			}
+		}
+		Ok(())
+	}
+
+	fn encode(mut self) -> AnyResult<T> {
 		loop {
 			match self.input.produce()? {
 				None => {
+					self.digest()?;
 					self.input.close()?;
 					self.encoder.close()?;
-					return Ok(());
+					return Ok(self.tracer);
 				}
 				Some(message) => match message.get() {
 					Message::Bit(context_index, bit) => self.bit(context_index, bit)?,
@@ -159,15 +199,25 @@ impl<const IO_BUFFER_SIZE: usize, const MESSAGE_BUFFER_SIZE: usize>
 
 // -----------------------------------------------
 
-fn run_secondary_context_encoder<const IO_BUFFER_SIZE: usize, const MESSAGE_BUFFER_SIZE: usize>(
+fn run_secondary_context_encoder<
+	T: Tracer,
+	const IO_BUFFER_SIZE: usize,
+	const MESSAGE_BUFFER_SIZE: usize,
+>(
 	input: BufferedInputPipe<PackedMessage, MESSAGE_BUFFER_SIZE>,
 	output: BufferedOutputPipe<u8, IO_BUFFER_SIZE>,
-) -> AnyResult<()> {
-	let encoder: SecondaryContextEncoder<IO_BUFFER_SIZE, MESSAGE_BUFFER_SIZE> =
+	config: &Config,
+	tracer: T,
+	digest: Receiver<Vec<u8>>,
+) -> AnyResult<T> {
+	let layout: ContextLayout = ContextLayout::new(config.resolved_context_size());
+	let encoder: SecondaryContextEncoder<T, IO_BUFFER_SIZE, MESSAGE_BUFFER_SIZE> =
 		SecondaryContextEncoder {
-			context: BridgedSecondaryContext::new(),
+			context: BridgedSecondaryContext::with_profile(layout.secondary_size(), config.profile),
 			input,
 			encoder: BitEncoder::new(output),
+			tracer,
+			digest,
 		};
 	encoder.encode()
 }
@@ -182,49 +232,140 @@ pub fn encode<
 >(
 	reader: R,
 	writer: W,
+	config: &Config,
 ) -> AnyResult<(R, W)> {
+	let (returned_reader, returned_writer, _): (R, W, ()) =
+		encode_counted::<R, W, (), IO_BUFFER_SIZE, MESSAGE_BUFFER_SIZE>(
+			reader,
+			writer,
+			config,
+			(),
+			pipe_tuning().pipe_depth,
+		)?;
+	Ok((returned_reader, returned_writer))
+}
+
+// Encode like encode() does, counting how the bytes matched, with pipes of the given depth
+pub fn encode_counted<
+	R: Read + Send,
+	W: Write + Send,
+	C: MatchCounter,
+	const IO_BUFFER_SIZE: usize,
+	const MESSAGE_BUFFER_SIZE: usize,
+>(
+	reader: R,
+	writer: W,
+	config: &Config,
+	counter: C,
+	depth: usize,
+) -> AnyResult<(R, W, C)> {
+	let (returned_reader, returned_writer, _, returned_counter): (R, W, (), C) =
+		encode_with_tracer::<R, W, (), C, IO_BUFFER_SIZE, MESSAGE_BUFFER_SIZE>(
+			reader,
+			writer,
+			config,
+			(),
+			counter,
+			depth,
+		)?;
+	Ok((returned_reader, returned_writer, returned_counter))
+}
+
+pub fn encode_with_tracer<
+	R: Read + Send,
+	W: Write + Send,
+	T: Tracer + Send,
+	C: MatchCounter,
+	const IO_BUFFER_SIZE: usize,
+	const MESSAGE_BUFFER_SIZE: usize,
+>(
+	reader: R,
+	writer: W,
+	config: &Config,
+	tracer: T,
+	counter: C,
+	depth: usize,
+) -> AnyResult<(R, W, T, C)> {
+	// the spans of the threads are children of this one
+	let span: PipelineSpan = pipeline_span!(
+		"encode",
+		profile = config.profile.name(),
+		checksum = config.checksum.name()
+	);
+	enter_span!(span);
 	scope(|scope| {
 		// create pipe between file reader thread and primary context thread
 		let (reader_output_pipe, reader_input_pipe): (
 			BufferedOutputPipe<u8, IO_BUFFER_SIZE>,
 			BufferedInputPipe<u8, IO_BUFFER_SIZE>,
-		) = pipe::<u8, IO_BUFFER_SIZE>();
+		) = pipe::<u8, IO_BUFFER_SIZE>(depth);
 
 		// create pipe between primary context thread and secondary context thread
 		let (message_writer, message_reader): (
 			BufferedOutputPipe<PackedMessage, MESSAGE_BUFFER_SIZE>,
 			BufferedInputPipe<PackedMessage, MESSAGE_BUFFER_SIZE>,
-		) = pipe::<PackedMessage, MESSAGE_BUFFER_SIZE>();
+		) = pipe::<PackedMessage, MESSAGE_BUFFER_SIZE>(depth);
 
 		// create pipe between secondary context thread and file writer thread
 		let (writer_output_pipe, writer_input_pipe): (
 			BufferedOutputPipe<u8, IO_BUFFER_SIZE>,
 			BufferedInputPipe<u8, IO_BUFFER_SIZE>,
-		) = pipe::<u8, IO_BUFFER_SIZE>();
+		) = pipe::<u8, IO_BUFFER_SIZE>(depth);
+
+		// create channel to hand the checksum digest from file reader thread to secondary context
+		// thread, as the digest is coded after the end of stream
+		let (digest_sender, digest_receiver): (Sender<Vec<u8>>, Receiver<Vec<u8>>) = channel();
 
 		// create file reader thread
-		let file_reader: ScopedJoinHandle<AnyResult<R>> =
-			scope.spawn(|| run_file_reader(reader, reader_output_pipe));
+		let reader_span: PipelineSpan = pipeline_span!("reader");
+		let file_reader: ScopedJoinHandle<AnyResult<R>> = scope.spawn(move || {
+			enter_span!(reader_span);
+			let digest_reader: DigestReader<R> = DigestReader::new(reader, config.checksum);
+			let (returned_reader, digest): (R, Vec<u8>) =
+				run_file_reader(digest_reader, reader_output_pipe)?.finish();
+			digest_sender.send(digest)?;
+			Ok(returned_reader)
+		});
 
 		// create primary context thread
-		let primary_context_encoder: ScopedJoinHandle<AnyResult<()>> =
-			scope.spawn(|| run_primary_context_encoder(reader_input_pipe, message_writer));
+		let primary_span: PipelineSpan = pipeline_span!("primary_context");
+		let primary_context_encoder: ScopedJoinHandle<AnyResult<C>> = scope.spawn(|| {
+			enter_span!(primary_span);
+			run_primary_context_encoder(reader_input_pipe, message_writer, config, counter)
+		});
 
 		// create secondary context thread
-		let secondary_context_encoder: ScopedJoinHandle<AnyResult<()>> =
-			scope.spawn(|| run_secondary_context_encoder(message_reader, writer_output_pipe));
+		let secondary_span: PipelineSpan = pipeline_span!("secondary_context");
+		let secondary_context_encoder: ScopedJoinHandle<AnyResult<T>> = scope.spawn(|| {
+			enter_span!(secondary_span);
+			run_secondary_context_encoder(
+				message_reader,
+				writer_output_pipe,
+				config,
+				tracer,
+				digest_receiver,
+			)
+		});
 
 		// create file writer thread
-		let file_writer: ScopedJoinHandle<AnyResult<W>> =
-			scope.spawn(|| run_file_writer(writer_input_pipe, writer));
+		let writer_span: PipelineSpan = pipeline_span!("writer");
+		let file_writer: ScopedJoinHandle<AnyResult<W>> = scope.spawn(|| {
+			enter_span!(writer_span);
+			run_file_writer(writer_input_pipe, writer)
+		});
 
 		// join all thread
 		let returned_reader: R = thread_join(file_reader)?;
-		thread_join(primary_context_encoder)?;
-		thread_join(secondary_context_encoder)?;
+		let returned_counter: C = thread_join(primary_context_encoder)?;
+		let returned_tracer: T = thread_join(secondary_context_encoder)?;
 		let returned_writer: W = thread_join(file_writer)?;
 
 		// give back the file handlers
-		Ok((returned_reader, returned_writer))
+		Ok((
+			returned_reader,
+			returned_writer,
+			returned_tracer,
+			returned_counter,
+		))
 	})
 }
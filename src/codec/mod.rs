@@ -21,6 +21,18 @@ mod bridged;
 mod decoder;
 mod encoder;
 mod shared;
+mod stats;
+mod tracer;
 
-pub use self::decoder::decode;
-pub use self::encoder::encode;
+#[cfg(feature = "trace")]
+mod trace;
+
+#[cfg(test)]
+mod test;
+
+pub use self::bridged::context_memory_usage;
+pub use self::decoder::{decode, decode_piped};
+pub use self::encoder::{encode, encode_counted};
+pub use self::stats::{MatchCounter, MatchStats};
+#[cfg(feature = "trace")]
+pub use self::trace::{encode_traced, TraceEvent, TraceLog, TraceSink};
@@ -0,0 +1,133 @@
+/*
+ * srx: The fast Symbol Ranking based compressor.
+ * Copyright (C) 2023-2024  Mai Thanh Minh (a.k.a. thanhminhmr)
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU General Public License as published by the Free Software
+ * Foundation, either  version 3 of the  License,  or (at your option) any later
+ * version.
+ *
+ * This program  is distributed in the hope  that it will be useful, but WITHOUT
+ * ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+ * FOR  A PARTICULAR PURPOSE. See  the  GNU  General  Public   License  for more
+ * details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+ */
+
+use crate::primary_context::ByteMatched;
+
+// -----------------------------------------------
+
+// Hook called by the primary context encoder for every byte with how it matched. The unit type is
+// the default counter: it does nothing and compiles away, like the unit tracer.
+pub trait MatchCounter: Default + Send {
+	fn matched(&mut self, byte: u8, matched: ByteMatched);
+
+	// add the counts of another part of the same data, as a blocked file codes each block apart
+	fn merge(&mut self, other: Self);
+}
+
+impl MatchCounter for () {
+	#[inline(always)]
+	fn matched(&mut self, _byte: u8, _matched: ByteMatched) {}
+
+	#[inline(always)]
+	fn merge(&mut self, _other: Self) {}
+}
+
+// -----------------------------------------------
+
+/// How the bytes of some data matched the candidates of their context while compressing. Data
+/// that compresses poorly has few matches, and its literals spread over many byte values.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct MatchStats {
+	/// Bytes that were the first candidate of their context.
+	pub match_first: u64,
+	/// Bytes that were the second candidate.
+	pub match_second: u64,
+	/// Bytes that were the third candidate.
+	pub match_third: u64,
+	/// Bytes that were none of the candidates, coded as literals.
+	pub no_match: u64,
+	/// How often each byte value was coded as a literal.
+	pub literals: [u64; 256],
+}
+
+impl MatchStats {
+	/// Number of bytes counted.
+	pub fn total(&self) -> u64 {
+		self.match_first + self.match_second + self.match_third + self.no_match
+	}
+
+	/// Share of the bytes in a class, from 0 to 1.
+	pub fn ratio(&self, count: u64) -> f64 {
+		match self.total() {
+			0 => 0.0,
+			total => count as f64 / total as f64,
+		}
+	}
+
+	/// Order 0 entropy of the literals in bits per literal, near 8 when they look random.
+	pub fn literal_entropy(&self) -> f64 {
+		let total: f64 = self.no_match as f64;
+		self.literals
+			.iter()
+			.filter(|count| **count != 0)
+			.map(|count| {
+				let probability: f64 = *count as f64 / total;
+				-probability * probability.log2()
+			})
+			.sum()
+	}
+
+	/// The most frequent literal byte values with their counts, most frequent first.
+	pub fn top_literals(&self, limit: usize) -> Vec<(u8, u64)> {
+		let mut literals: Vec<(u8, u64)> = (0..=255)
+			.zip(self.literals)
+			.filter(|(_, count)| *count != 0)
+			.collect();
+		literals.sort_by(|left, right| right.1.cmp(&left.1).then(left.0.cmp(&right.0)));
+		literals.truncate(limit);
+		literals
+	}
+}
+
+impl Default for MatchStats {
+	fn default() -> Self {
+		Self {
+			match_first: 0,
+			match_second: 0,
+			match_third: 0,
+			no_match: 0,
+			literals: [0; 256],
+		}
+	}
+}
+
+impl MatchCounter for MatchStats {
+	#[inline(always)]
+	fn matched(&mut self, byte: u8, matched: ByteMatched) {
+		match matched {
+			ByteMatched::MatchFirst => self.match_first += 1,
+			ByteMatched::MatchSecond => self.match_second += 1,
+			ByteMatched::MatchThird => self.match_third += 1,
+			ByteMatched::NoMatch => {
+				self.no_match += 1;
+				self.literals[byte as usize] += 1;
+			}
+		}
+	}
+
+	fn merge(&mut self, other: Self) {
+		self.match_first += other.match_first;
+		self.match_second += other.match_second;
+		self.match_third += other.match_third;
+		self.no_match += other.no_match;
+		for (count, other_count) in self.literals.iter_mut().zip(other.literals) {
+			*count += other_count;
+		}
+	}
+}
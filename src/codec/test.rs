@@ -0,0 +1,139 @@
+/*
+ * srx: The fast Symbol Ranking based compressor.
+ * Copyright (C) 2023-2024  Mai Thanh Minh (a.k.a. thanhminhmr)
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU General Public License as published by the Free Software
+ * Foundation, either  version 3 of the  License,  or (at your option) any later
+ * version.
+ *
+ * This program  is distributed in the hope  that it will be useful, but WITHOUT
+ * ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+ * FOR  A PARTICULAR PURPOSE. See  the  GNU  General  Public   License  for more
+ * details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+ */
+
+use super::{decode, encode};
+use crate::basic::AnyResult;
+use crate::checksum::Checksum;
+use crate::config::{Config, ContextSize};
+use crate::secondary_context::AdaptationProfile;
+use std::io::Cursor;
+
+// -----------------------------------------------
+
+const IO_BUFFER_SIZE: usize = 0x10000;
+const MESSAGE_BUFFER_SIZE: usize = 0x4000;
+
+fn round_trip_with(data: &[u8], config: &Config) -> AnyResult<Vec<u8>> {
+	let (_, compressed): (Cursor<&[u8]>, Vec<u8>) =
+		encode::<_, _, IO_BUFFER_SIZE, MESSAGE_BUFFER_SIZE>(Cursor::new(data), Vec::new(), config)?;
+	let (_, decompressed): (Cursor<Vec<u8>>, Vec<u8>) =
+		decode::<_, _, IO_BUFFER_SIZE>(Cursor::new(compressed), Vec::new(), config)?;
+	Ok(decompressed)
+}
+
+fn round_trip(data: &[u8]) -> AnyResult<Vec<u8>> {
+	round_trip_with(data, &Config::default())
+}
+
+// -----------------------------------------------
+
+#[test]
+fn test_round_trip() -> AnyResult<()> {
+	let data: Vec<u8> = (0..0x30000_usize)
+		.map(|index| ((index * index) >> 7) as u8)
+		.collect();
+	assert_eq!(round_trip(&data)?, data);
+	assert_eq!(round_trip(&[])?, Vec::<u8>::new());
+	Ok(())
+}
+
+#[test]
+fn test_round_trip_with_profiles() -> AnyResult<()> {
+	let data: Vec<u8> = b"the quick brown fox jumps over the lazy dog; ".repeat(256);
+	for profile in AdaptationProfile::ALL {
+		let config: Config = Config {
+			profile,
+			..Config::default()
+		};
+		assert_eq!(round_trip_with(&data, &config)?, data);
+	}
+	Ok(())
+}
+
+#[test]
+fn test_round_trip_with_context_sizes() -> AnyResult<()> {
+	let data: Vec<u8> = (0..0x20000_usize)
+		.map(|index| ((index % 251) ^ (index >> 9)) as u8)
+		.collect();
+	for context_size in [
+		ContextSize::MIN,
+		ContextSize::for_size(0x20000),
+		ContextSize::MAX,
+	] {
+		let config: Config = Config {
+			context_size: Some(context_size),
+			..Config::default()
+		};
+		assert_eq!(round_trip_with(&data, &config)?, data);
+	}
+	Ok(())
+}
+
+#[test]
+fn test_checksum_detects_corruption() -> AnyResult<()> {
+	let data: Vec<u8> = b"the quick brown fox jumps over the lazy dog; ".repeat(256);
+	for checksum in Checksum::ALL {
+		let config: Config = Config {
+			checksum,
+			..Config::default()
+		};
+		assert_eq!(round_trip_with(&data, &config)?, data);
+	}
+
+	// flip a bit in the middle of the stream, the decoded data goes wrong and so does its digest
+	let config: Config = Config {
+		checksum: Checksum::Xxh3,
+		..Config::default()
+	};
+	let (_, mut compressed): (Cursor<&[u8]>, Vec<u8>) =
+		encode::<_, _, IO_BUFFER_SIZE, MESSAGE_BUFFER_SIZE>(
+			Cursor::new(data.as_slice()),
+			Vec::new(),
+			&config,
+		)?;
+	let middle: usize = compressed.len() / 2;
+	compressed[middle] ^= 0x10;
+	assert!(decode::<_, _, IO_BUFFER_SIZE>(Cursor::new(compressed), Vec::new(), &config).is_err());
+	Ok(())
+}
+
+#[cfg(feature = "trace")]
+#[test]
+fn test_trace_one_event_per_byte() -> AnyResult<()> {
+	use super::{encode_traced, TraceEvent};
+
+	let data: Vec<u8> = b"abracadabra, abracadabra, abracadabra!".repeat(64);
+	let mut events: Vec<TraceEvent> = Vec::new();
+	let (_, compressed, _) = encode_traced::<_, _, _, IO_BUFFER_SIZE, MESSAGE_BUFFER_SIZE>(
+		Cursor::new(&data),
+		Vec::new(),
+		&Config::default(),
+		|event: &TraceEvent| events.push(*event),
+	)?;
+
+	// the end of stream marker is not reported, and the code lengths add up to the output size
+	assert_eq!(events.len(), data.len());
+	assert!(events
+		.iter()
+		.enumerate()
+		.all(|(index, event)| event.position == index as u64));
+	let code_length: f64 = events.iter().map(|event| event.code_length).sum();
+	assert!(code_length <= (compressed.len() * 8) as f64);
+	Ok(())
+}
@@ -0,0 +1,213 @@
+/*
+ * srx: The fast Symbol Ranking based compressor.
+ * Copyright (C) 2023-2024  Mai Thanh Minh (a.k.a. thanhminhmr)
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU General Public License as published by the Free Software
+ * Foundation, either  version 3 of the  License,  or (at your option) any later
+ * version.
+ *
+ * This program  is distributed in the hope  that it will be useful, but WITHOUT
+ * ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+ * FOR  A PARTICULAR PURPOSE. See  the  GNU  General  Public   License  for more
+ * details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+ */
+
+use super::encoder::encode_with_tracer;
+use super::tracer::Tracer;
+use crate::basic::{pipe_tuning, AnyResult, Bit};
+use crate::config::Config;
+use crate::primary_context::ByteMatched;
+use std::io::{Read, Write};
+
+// -----------------------------------------------
+
+// One event per encoded byte
+#[derive(Copy, Clone, Debug)]
+pub struct TraceEvent {
+	// position of the byte in the uncompressed input
+	pub position: u64,
+	// how the primary context predicted the byte
+	pub matched: ByteMatched,
+	// secondary context index of the first coded bit
+	pub context_index: usize,
+	// prediction (probability of a one bit, scaled to 2^32) of the first coded bit
+	pub prediction: u32,
+	// number of bits spent to code the byte
+	pub code_length: f64,
+}
+
+// Receiver of trace events, any FnMut(&TraceEvent) closure is one
+pub trait TraceSink {
+	fn event(&mut self, event: &TraceEvent) -> AnyResult<()>;
+}
+
+impl<F: FnMut(&TraceEvent)> TraceSink for F {
+	fn event(&mut self, event: &TraceEvent) -> AnyResult<()> {
+		self(event);
+		Ok(())
+	}
+}
+
+// -----------------------------------------------
+
+// A compact binary log of trace events, 13 bytes per event: the matched class (0: first, 1: none,
+// 2: second, 3: third), the context index (u32), the prediction (u32) and the code length in bits
+// (f32), all in little endian. The position is implied by the order of the records.
+pub struct TraceLog<W: Write> {
+	writer: W,
+}
+
+impl<W: Write> TraceLog<W> {
+	pub fn new(writer: W) -> Self {
+		Self { writer }
+	}
+
+	pub fn into_inner(self) -> W {
+		self.writer
+	}
+}
+
+impl<W: Write> TraceSink for TraceLog<W> {
+	fn event(&mut self, event: &TraceEvent) -> AnyResult<()> {
+		let mut record: [u8; 13] = [0; 13];
+		record[0] = match event.matched {
+			ByteMatched::MatchFirst => 0,
+			ByteMatched::NoMatch => 1,
+			ByteMatched::MatchSecond => 2,
+			ByteMatched::MatchThird => 3,
+		};
+		record[1..5].copy_from_slice(&(event.context_index as u32).to_le_bytes());
+		record[5..9].copy_from_slice(&event.prediction.to_le_bytes());
+		record[9..13].copy_from_slice(&(event.code_length as f32).to_le_bytes());
+		self.writer.write_all(&record)?;
+		Ok(())
+	}
+}
+
+// -----------------------------------------------
+
+// Which bit of the byte coding sequence comes next
+#[derive(Copy, Clone)]
+enum Step {
+	First,
+	Second,
+	Third,
+	Literal(u8),
+}
+
+// Rebuild the per-byte events from the stream of coded bits. The events are delayed by one so that
+// the last one, which is the end of stream marker and not an input byte, can be dropped.
+struct ByteTracer<S: TraceSink> {
+	sink: S,
+	step: Step,
+	current: TraceEvent,
+	pending: Option<TraceEvent>,
+}
+
+impl<S: TraceSink> ByteTracer<S> {
+	fn new(sink: S) -> Self {
+		Self {
+			sink,
+			step: Step::First,
+			current: TraceEvent {
+				position: 0,
+				matched: ByteMatched::MatchFirst,
+				context_index: 0,
+				prediction: 0,
+				code_length: 0.0,
+			},
+			pending: None,
+		}
+	}
+
+	fn into_sink(self) -> S {
+		self.sink
+	}
+
+	fn complete(&mut self, matched: ByteMatched) -> AnyResult<()> {
+		self.current.matched = matched;
+		if let Some(event) = self.pending.replace(self.current) {
+			self.sink.event(&event)?;
+		}
+		self.current.position += 1;
+		self.step = Step::First;
+		Ok(())
+	}
+}
+
+impl<S: TraceSink> Tracer for ByteTracer<S> {
+	fn bit(&mut self, context_index: usize, prediction: u32, bit: Bit) -> AnyResult<()> {
+		// the prediction is the probability of a one bit
+		let probability: f64 = prediction as f64 / (1u64 << 32) as f64;
+		let code_length: f64 = -match bit {
+			Bit::Zero => 1.0 - probability,
+			Bit::One => probability,
+		}
+		.log2();
+		match self.step {
+			Step::First => {
+				self.current.context_index = context_index;
+				self.current.prediction = prediction;
+				self.current.code_length = code_length;
+				match bit {
+					Bit::Zero => self.complete(ByteMatched::MatchFirst)?,
+					Bit::One => self.step = Step::Second,
+				}
+			}
+			Step::Second => {
+				self.current.code_length += code_length;
+				self.step = match bit {
+					Bit::Zero => Step::Literal(8),
+					Bit::One => Step::Third,
+				};
+			}
+			Step::Third => {
+				self.current.code_length += code_length;
+				match bit {
+					Bit::Zero => self.complete(ByteMatched::MatchSecond)?,
+					Bit::One => self.complete(ByteMatched::MatchThird)?,
+				}
+			}
+			Step::Literal(remaining) => {
+				self.current.code_length += code_length;
+				match remaining {
+					1 => self.complete(ByteMatched::NoMatch)?,
+					_ => self.step = Step::Literal(remaining - 1),
+				}
+			}
+		}
+		Ok(())
+	}
+}
+
+// -----------------------------------------------
+
+// Encode like encode() does, reporting one event per input byte to the sink
+pub fn encode_traced<
+	R: Read + Send,
+	W: Write + Send,
+	S: TraceSink + Send,
+	const IO_BUFFER_SIZE: usize,
+	const MESSAGE_BUFFER_SIZE: usize,
+>(
+	reader: R,
+	writer: W,
+	config: &Config,
+	sink: S,
+) -> AnyResult<(R, W, S)> {
+	let (returned_reader, returned_writer, tracer, _): (R, W, ByteTracer<S>, ()) =
+		encode_with_tracer::<R, W, ByteTracer<S>, (), IO_BUFFER_SIZE, MESSAGE_BUFFER_SIZE>(
+			reader,
+			writer,
+			config,
+			ByteTracer::new(sink),
+			(),
+			pipe_tuning().pipe_depth,
+		)?;
+	Ok((returned_reader, returned_writer, tracer.into_sink()))
+}
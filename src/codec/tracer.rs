@@ -0,0 +1,35 @@
+/*
+ * srx: The fast Symbol Ranking based compressor.
+ * Copyright (C) 2023-2024  Mai Thanh Minh (a.k.a. thanhminhmr)
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU General Public License as published by the Free Software
+ * Foundation, either  version 3 of the  License,  or (at your option) any later
+ * version.
+ *
+ * This program  is distributed in the hope  that it will be useful, but WITHOUT
+ * ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+ * FOR  A PARTICULAR PURPOSE. See  the  GNU  General  Public   License  for more
+ * details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+ */
+
+use crate::basic::{AnyResult, Bit};
+
+// -----------------------------------------------
+
+// Hook called by the secondary context encoder for every coded bit. The unit type is the default
+// tracer: it does nothing and compiles away, so the hot loop is unchanged when tracing is not used.
+pub trait Tracer {
+	fn bit(&mut self, context_index: usize, prediction: u32, bit: Bit) -> AnyResult<()>;
+}
+
+impl Tracer for () {
+	#[inline(always)]
+	fn bit(&mut self, _context_index: usize, _prediction: u32, _bit: Bit) -> AnyResult<()> {
+		Ok(())
+	}
+}
@@ -0,0 +1,124 @@
+/*
+ * srx: The fast Symbol Ranking based compressor.
+ * Copyright (C) 2023-2024  Mai Thanh Minh (a.k.a. thanhminhmr)
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU General Public License as published by the Free Software
+ * Foundation, either  version 3 of the  License,  or (at your option) any later
+ * version.
+ *
+ * This program  is distributed in the hope  that it will be useful, but WITHOUT
+ * ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+ * FOR  A PARTICULAR PURPOSE. See  the  GNU  General  Public   License  for more
+ * details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+ */
+
+use crate::basic::LOW_MEMORY;
+use crate::checksum::Checksum;
+use crate::secondary_context::AdaptationProfile;
+
+// -----------------------------------------------
+
+/// Parameters of the compression. They are recorded in the file header, so decompression always
+/// runs with the same parameters as the compression did.
+#[derive(Clone, Eq, PartialEq, Debug, Default)]
+pub struct Config {
+	/// Adaptation profile of the secondary context.
+	pub profile: AdaptationProfile,
+	/// Checksum of the original data, verified on decompression.
+	pub checksum: Checksum,
+	/// Size of the original data when it is known before compression, it is checked on both ends.
+	pub content_size: Option<u64>,
+	/// Expected size of the original data when the exact size is not known, it is only used to
+	/// choose the context size and is not recorded.
+	pub size_hint: Option<u64>,
+	/// Size of the context tables, chosen from `content_size` or `size_hint` when not set.
+	pub context_size: Option<ContextSize>,
+	/// Split the data into blocks of this size, each compressed on its own, so that any of them
+	/// can be decompressed without the others (see [`SrxSeekableReader`](crate::SrxSeekableReader)).
+	pub block_size: Option<u64>,
+	/// Start each block with a marker and checksums of its own, so that the blocks can still be
+	/// found in a damaged file, or in the middle of a stream (see [`recover`](crate::recover)).
+	pub sync_markers: bool,
+}
+
+impl Config {
+	/// The context size used with this config.
+	pub fn resolved_context_size(&self) -> ContextSize {
+		// each block has its own tables, they never see more than a block of data
+		let size: Option<u64> = match (self.content_size.or(self.size_hint), self.block_size) {
+			(Some(size), Some(block_size)) => Some(size.min(block_size)),
+			(size, block_size) => size.or(block_size),
+		};
+		match (self.context_size, size) {
+			(Some(context_size), _) => context_size,
+			(None, Some(size)) => ContextSize::for_size(size),
+			(None, None) => ContextSize::DEFAULT,
+		}
+	}
+}
+
+// -----------------------------------------------
+
+/// Size of the context tables, as the log2 of their number of entries.
+///
+/// A table much larger than the data is never filled, but it still has to be allocated and zeroed,
+/// which takes longer than compressing a small file.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct ContextSize {
+	/// Entries of the primary context, 4 bytes each.
+	pub primary_bits: u8,
+	/// Literal contexts of the secondary context, 256 bit predictors each.
+	pub literal_bits: u8,
+}
+
+impl ContextSize {
+	/// The smallest tables.
+	pub const MIN: ContextSize = ContextSize {
+		primary_bits: 12,
+		literal_bits: 4,
+	};
+
+	/// The largest tables, about 74 MiB. Files without a recorded context size use them.
+	pub const MAX: ContextSize = ContextSize {
+		primary_bits: 24,
+		literal_bits: 14,
+	};
+
+	/// Tables of about 20 MiB, for targets with little memory.
+	pub const LOW_MEMORY: ContextSize = ContextSize {
+		primary_bits: 22,
+		literal_bits: 12,
+	};
+
+	/// The tables used when the size of the data is not known, and the largest ones chosen for a
+	/// known size: [`MAX`](Self::MAX), or [`LOW_MEMORY`](Self::LOW_MEMORY) on 32-bit targets and
+	/// with the `low-memory` feature.
+	pub const DEFAULT: ContextSize = if LOW_MEMORY {
+		Self::LOW_MEMORY
+	} else {
+		Self::MAX
+	};
+
+	/// Tables just large enough for this much data.
+	pub fn for_size(size: u64) -> Self {
+		// about two primary entries per byte, and one literal context per 1024 bytes
+		let bits: u8 = (u64::BITS - size.leading_zeros()) as u8;
+		Self {
+			primary_bits: (bits + 1).clamp(Self::MIN.primary_bits, Self::DEFAULT.primary_bits),
+			literal_bits: bits
+				.saturating_sub(10)
+				.clamp(Self::MIN.literal_bits, Self::DEFAULT.literal_bits),
+		}
+	}
+
+	/// Whether the sizes are between [`MIN`](Self::MIN) and [`MAX`](Self::MAX).
+	pub fn is_valid(self) -> bool {
+		(Self::MIN.primary_bits..=Self::MAX.primary_bits).contains(&self.primary_bits)
+			&& (Self::MIN.literal_bits..=Self::MAX.literal_bits).contains(&self.literal_bits)
+	}
+}
@@ -0,0 +1,551 @@
+/*
+ * srx: The fast Symbol Ranking based compressor.
+ * Copyright (C) 2023-2024  Mai Thanh Minh (a.k.a. thanhminhmr)
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU General Public License as published by the Free Software
+ * Foundation, either  version 3 of the  License,  or (at your option) any later
+ * version.
+ *
+ * This program  is distributed in the hope  that it will be useful, but WITHOUT
+ * ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+ * FOR  A PARTICULAR PURPOSE. See  the  GNU  General  Public   License  for more
+ * details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+ */
+
+use super::counter::Counter;
+use super::{decode_tuned, encode_tuned, verify_tuned};
+use crate::basic::{pipe_tuning, pipeline_event, AnyError, AnyResult};
+use crate::codec::MatchCounter;
+use crate::config::Config;
+use std::io::{copy, sink, Cursor, Error, ErrorKind, Read, Seek, SeekFrom, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{sync_channel, Receiver, SyncSender};
+use std::sync::Mutex;
+use std::thread::{scope, ScopedJoinHandle};
+
+// -----------------------------------------------
+
+// A blocked file is the header followed by the blocks, each coded on its own, an end marker, the
+// index of the blocks and a trailer pointing at the index:
+//   block   := original size, compressed size, coded data
+//   end     := 0
+//   index   := [offset of the block, original size, compressed size]...
+//   trailer := number of blocks, INDEX_MAGIC
+// With sync markers, a block (and the end marker, which is a block of nothing) starts with a header
+// that can be found and checked without the rest of the file:
+//   block   := SYNC_MAGIC, original offset, original size, compressed size, data crc, header crc,
+//              coded data
+// All numbers are u64 little endian, except the crc32s which are u32. Reading the blocks in order
+// never needs the index, it is there for readers that seek.
+
+const INDEX_MAGIC: &[u8; 8] = b"sRxIndex";
+pub const SYNC_MAGIC: &[u8; 8] = b"sRx\xA5Sync";
+
+const INDEX_ENTRY_SIZE: u64 = 24;
+// the most memory taken up front for a decoded block, its size comes from the file
+const PREALLOCATED_SIZE: usize = 64 << 20;
+const TRAILER_SIZE: u64 = 16;
+pub const SYNC_HEADER_SIZE: usize = 40;
+
+// -----------------------------------------------
+
+// the coded data of a block waiting to be checked, with the size of its original data
+type PendingBlock = (Vec<u8>, u64);
+
+// -----------------------------------------------
+
+/// Where a block of a blocked srx file is, and what it holds.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct BlockInfo {
+	/// Offset of the block in the srx file.
+	pub offset: u64,
+	/// Offset of its data in the original data.
+	pub original_offset: u64,
+	/// Size of its data.
+	pub original_size: u64,
+	/// Size of its coded data.
+	pub compressed_size: u64,
+}
+
+// the fields in front of the coded data of a block, the offset and the crc are only stored with
+// sync markers
+#[derive(Copy, Clone)]
+pub struct BlockHeader {
+	pub original_offset: u64,
+	pub original_size: u64,
+	pub compressed_size: u64,
+	pub data_crc: u32,
+}
+
+impl BlockHeader {
+	fn sync_bytes(&self) -> [u8; SYNC_HEADER_SIZE] {
+		let mut bytes: [u8; SYNC_HEADER_SIZE] = [0; SYNC_HEADER_SIZE];
+		bytes[0..8].copy_from_slice(SYNC_MAGIC);
+		bytes[8..16].copy_from_slice(&self.original_offset.to_le_bytes());
+		bytes[16..24].copy_from_slice(&self.original_size.to_le_bytes());
+		bytes[24..32].copy_from_slice(&self.compressed_size.to_le_bytes());
+		bytes[32..36].copy_from_slice(&self.data_crc.to_le_bytes());
+		let header_crc: u32 = crc32fast::hash(&bytes[0..36]);
+		bytes[36..40].copy_from_slice(&header_crc.to_le_bytes());
+		bytes
+	}
+
+	// a header found at the start of the bytes, if the marker and the header crc are right
+	pub fn from_sync_bytes(bytes: &[u8]) -> Option<Self> {
+		let field = |start: usize| -> u64 {
+			u64::from_le_bytes(bytes[start..start + 8].try_into().unwrap())
+		};
+		let header_crc: u32 = u32::from_le_bytes(bytes[36..40].try_into().unwrap());
+		if !bytes[0..8].eq(SYNC_MAGIC) || crc32fast::hash(&bytes[0..36]) != header_crc {
+			return None;
+		}
+		Some(Self {
+			original_offset: field(8),
+			original_size: field(16),
+			compressed_size: field(24),
+			data_crc: u32::from_le_bytes(bytes[32..36].try_into().unwrap()),
+		})
+	}
+
+	fn write<W: Write>(&self, writer: &mut W, sync: bool) -> AnyResult<()> {
+		if sync {
+			writer.write_all(&self.sync_bytes())?;
+		} else if self.original_size == 0 {
+			writer.write_all(&0_u64.to_le_bytes())?;
+		} else {
+			writer.write_all(&self.original_size.to_le_bytes())?;
+			writer.write_all(&self.compressed_size.to_le_bytes())?;
+		}
+		Ok(())
+	}
+
+	// the header of the next block, none at the end marker, a block cannot be larger than the
+	// block size of the file, which is checked before anything of that size is allocated
+	fn read<R: Read>(
+		reader: &mut R,
+		sync: bool,
+		block_size: u64,
+		original_offset: u64,
+	) -> AnyResult<Option<Self>> {
+		let header: Self = if sync {
+			let mut bytes: [u8; SYNC_HEADER_SIZE] = [0; SYNC_HEADER_SIZE];
+			reader.read_exact(&mut bytes)?;
+			match Self::from_sync_bytes(&bytes) {
+				Some(header) if header.original_offset == original_offset => header,
+				_ => return Err(AnyError::from_string("Corrupted SRX block!")),
+			}
+		} else {
+			let original_size: u64 = read_u64(reader)?;
+			Self {
+				original_offset,
+				original_size,
+				compressed_size: if original_size == 0 {
+					0
+				} else {
+					read_u64(reader)?
+				},
+				data_crc: 0,
+			}
+		};
+		if header.original_size > block_size {
+			return Err(AnyError::from_string("Corrupted SRX block!"));
+		}
+		Ok((header.original_size != 0).then_some(header))
+	}
+}
+
+// -----------------------------------------------
+
+fn read_u64<R: Read>(reader: &mut R) -> AnyResult<u64> {
+	let mut buffer: [u8; 8] = [0; 8];
+	reader.read_exact(&mut buffer)?;
+	Ok(u64::from_le_bytes(buffer))
+}
+
+// the original offset after a block, the sizes come from the file and may be anything
+fn next_offset(original_offset: u64, original_size: u64) -> AnyResult<u64> {
+	original_offset
+		.checked_add(original_size)
+		.ok_or_else(|| AnyError::from_string("Corrupted SRX block!"))
+}
+
+// the config a single block is coded with, the parameters of the whole file stay in the header
+pub fn block_config(config: &Config) -> Config {
+	Config {
+		content_size: None,
+		size_hint: None,
+		context_size: Some(config.resolved_context_size()),
+		block_size: None,
+		sync_markers: false,
+		..config.clone()
+	}
+}
+
+// a block is held in memory at once, so its sizes have to fit in an usize (4 GiB on 32-bit targets)
+pub fn in_memory_size(size: u64) -> AnyResult<usize> {
+	usize::try_from(size)
+		.map_err(|_| AnyError::from_string("SRX block too large for this platform!"))
+}
+
+pub fn encode_block<C: MatchCounter>(
+	data: &[u8],
+	config: &Config,
+	counter: C,
+) -> AnyResult<(Vec<u8>, C)> {
+	let (_, compressed, counter): (Cursor<&[u8]>, Vec<u8>, C) = encode_tuned(
+		Cursor::new(data),
+		Vec::new(),
+		config,
+		counter,
+		&pipe_tuning(),
+	)?;
+	pipeline_event!(
+		original_size = data.len(),
+		compressed_size = compressed.len(),
+		"block compressed"
+	);
+	Ok((compressed, counter))
+}
+
+pub fn decode_block(compressed: &[u8], original_size: u64, config: &Config) -> AnyResult<Vec<u8>> {
+	let limit: usize = in_memory_size(original_size)?;
+	let (_, BlockData { data, .. }): (Cursor<&[u8]>, BlockData) = decode_tuned(
+		Cursor::new(compressed),
+		BlockData {
+			data: Vec::with_capacity(limit.min(PREALLOCATED_SIZE)),
+			limit,
+		},
+		config,
+		&pipe_tuning(),
+	)?;
+	if data.len() != limit {
+		return Err(AnyError::from_string("Corrupted SRX block!"));
+	}
+	pipeline_event!(
+		original_size,
+		compressed_size = compressed.len(),
+		"block decompressed"
+	);
+	Ok(data)
+}
+
+// decode a block without keeping its data, only to check it
+pub fn verify_block(compressed: &[u8], original_size: u64, config: &Config) -> AnyResult<()> {
+	let (_, length): (Cursor<&[u8]>, u64) =
+		verify_tuned(Cursor::new(compressed), config, &pipe_tuning())?;
+	if length != original_size {
+		return Err(AnyError::from_string("Corrupted SRX block!"));
+	}
+	pipeline_event!(
+		original_size,
+		compressed_size = compressed.len(),
+		"block verified"
+	);
+	Ok(())
+}
+
+// -----------------------------------------------
+
+// the data of a block being decoded, corrupted coded data may decode to anything, so it cannot grow
+// past the size in the block header
+struct BlockData {
+	data: Vec<u8>,
+	limit: usize,
+}
+
+impl Write for BlockData {
+	fn write(&mut self, buffer: &[u8]) -> std::io::Result<usize> {
+		if buffer.len() > self.limit - self.data.len() {
+			return Err(Error::new(ErrorKind::InvalidData, "Corrupted SRX block!"));
+		}
+		self.data.extend_from_slice(buffer);
+		Ok(buffer.len())
+	}
+
+	fn flush(&mut self) -> std::io::Result<()> {
+		Ok(())
+	}
+}
+
+// -----------------------------------------------
+
+pub fn write_block<W: Write>(
+	writer: &mut Counter<W>,
+	original_size: u64,
+	compressed: &[u8],
+	blocks: &mut Vec<BlockInfo>,
+	sync: bool,
+) -> AnyResult<()> {
+	let original_offset: u64 = blocks
+		.last()
+		.map_or(0, |block| block.original_offset + block.original_size);
+	blocks.push(BlockInfo {
+		offset: writer.count(),
+		original_offset,
+		original_size,
+		compressed_size: compressed.len() as u64,
+	});
+	let header: BlockHeader = BlockHeader {
+		original_offset,
+		original_size,
+		compressed_size: compressed.len() as u64,
+		data_crc: if sync { crc32fast::hash(compressed) } else { 0 },
+	};
+	header.write(writer, sync)?;
+	writer.write_all(compressed)?;
+	Ok(())
+}
+
+// the end marker, the index and the trailer
+pub fn write_index<W: Write>(
+	writer: &mut Counter<W>,
+	blocks: &[BlockInfo],
+	sync: bool,
+) -> AnyResult<()> {
+	let end: BlockHeader = BlockHeader {
+		original_offset: blocks
+			.last()
+			.map_or(0, |block| block.original_offset + block.original_size),
+		original_size: 0,
+		compressed_size: 0,
+		data_crc: 0,
+	};
+	end.write(writer, sync)?;
+	for block in blocks {
+		writer.write_all(&block.offset.to_le_bytes())?;
+		writer.write_all(&block.original_size.to_le_bytes())?;
+		writer.write_all(&block.compressed_size.to_le_bytes())?;
+	}
+	writer.write_all(&(blocks.len() as u64).to_le_bytes())?;
+	writer.write_all(INDEX_MAGIC)?;
+	Ok(())
+}
+
+// the header and the coded data of the next block, none at the end marker
+pub fn read_block<R: Read>(
+	reader: &mut R,
+	sync: bool,
+	block_size: u64,
+	original_offset: u64,
+) -> AnyResult<Option<(BlockHeader, Vec<u8>)>> {
+	let header: BlockHeader = match BlockHeader::read(reader, sync, block_size, original_offset)? {
+		Some(header) => header,
+		None => return Ok(None),
+	};
+	in_memory_size(header.compressed_size)?;
+	let mut compressed: Vec<u8> = Vec::new();
+	reader
+		.take(header.compressed_size)
+		.read_to_end(&mut compressed)?;
+	if compressed.len() as u64 != header.compressed_size {
+		return Err(AnyError::from_string("Unexpected end of SRX stream!"));
+	}
+	if sync && crc32fast::hash(&compressed) != header.data_crc {
+		return Err(AnyError::from_string("Corrupted SRX block!"));
+	}
+	Ok(Some((header, compressed)))
+}
+
+// find the index from the end of the file, its entries are checked like the block headers are
+pub fn read_index<R: Read + Seek>(reader: &mut R, block_size: u64) -> AnyResult<Vec<BlockInfo>> {
+	let end: u64 = reader.seek(SeekFrom::End(0))?;
+	if end < TRAILER_SIZE {
+		return Err(AnyError::from_string("Missing SRX block index!"));
+	}
+	reader.seek(SeekFrom::Start(end - TRAILER_SIZE))?;
+	let count: u64 = read_u64(reader)?;
+	let mut magic: [u8; 8] = [0; 8];
+	reader.read_exact(&mut magic)?;
+	if !magic.eq(INDEX_MAGIC) || count > (end - TRAILER_SIZE) / INDEX_ENTRY_SIZE {
+		return Err(AnyError::from_string("Missing SRX block index!"));
+	}
+	reader.seek(SeekFrom::Start(
+		end - TRAILER_SIZE - count * INDEX_ENTRY_SIZE,
+	))?;
+	let mut blocks: Vec<BlockInfo> = Vec::with_capacity(count as usize);
+	let mut original_offset: u64 = 0;
+	for _ in 0..count {
+		let block: BlockInfo = BlockInfo {
+			offset: read_u64(reader)?,
+			original_offset,
+			original_size: read_u64(reader)?,
+			compressed_size: read_u64(reader)?,
+		};
+		original_offset = match original_offset.checked_add(block.original_size) {
+			Some(next) if block.original_size <= block_size => next,
+			_ => return Err(AnyError::from_string("Corrupted SRX block index!")),
+		};
+		blocks.push(block);
+	}
+	Ok(blocks)
+}
+
+// the coded data of a block found in the index, the offsets in the index are from the start of the
+// srx file, which is at the base offset of the reader
+pub fn read_block_at<R: Read + Seek>(
+	reader: &mut R,
+	base: u64,
+	block: &BlockInfo,
+	sync: bool,
+	block_size: u64,
+) -> AnyResult<Vec<u8>> {
+	let offset: u64 = base
+		.checked_add(block.offset)
+		.ok_or_else(|| AnyError::from_string("Corrupted SRX block index!"))?;
+	reader.seek(SeekFrom::Start(offset))?;
+	match read_block(reader, sync, block_size, block.original_offset)? {
+		Some((header, compressed))
+			if header.original_size == block.original_size
+				&& header.compressed_size == block.compressed_size =>
+		{
+			Ok(compressed)
+		}
+		_ => Err(AnyError::from_string("Corrupted SRX block index!")),
+	}
+}
+
+// -----------------------------------------------
+
+pub fn compress_blocks<R: Read, W: Write, C: MatchCounter>(
+	mut reader: R,
+	mut writer: Counter<W>,
+	config: &Config,
+	block_size: u64,
+	mut counter: C,
+) -> AnyResult<(R, Counter<W>, C)> {
+	if block_size == 0 || block_size > usize::MAX as u64 {
+		return Err(AnyError::from_string("Unsupported block size!"));
+	}
+	let block_config: Config = block_config(config);
+	let mut blocks: Vec<BlockInfo> = Vec::new();
+	let mut data: Vec<u8> = Vec::new();
+	loop {
+		data.clear();
+		if reader.by_ref().take(block_size).read_to_end(&mut data)? == 0 {
+			break;
+		}
+		let (compressed, block_counter): (Vec<u8>, C) =
+			encode_block(&data, &block_config, C::default())?;
+		counter.merge(block_counter);
+		write_block(
+			&mut writer,
+			data.len() as u64,
+			&compressed,
+			&mut blocks,
+			config.sync_markers,
+		)?;
+	}
+	write_index(&mut writer, &blocks, config.sync_markers)?;
+	Ok((reader, writer, counter))
+}
+
+pub fn decompress_blocks<R: Read, W: Write>(
+	mut reader: R,
+	mut writer: W,
+	config: &Config,
+	block_size: u64,
+) -> AnyResult<(R, W)> {
+	let block_config: Config = block_config(config);
+	let mut original_offset: u64 = 0;
+	while let Some((header, compressed)) = read_block(
+		&mut reader,
+		config.sync_markers,
+		block_size,
+		original_offset,
+	)? {
+		writer.write_all(&decode_block(
+			&compressed,
+			header.original_size,
+			&block_config,
+		)?)?;
+		// a block is all a streaming writer sends at once, it is passed on as soon as it is there
+		writer.flush()?;
+		original_offset = next_offset(original_offset, header.original_size)?;
+	}
+	// the index is of no use here, but the whole file is read like an unblocked one is
+	copy(&mut reader, &mut sink())?;
+	Ok((reader, writer))
+}
+
+// check every block, returning the size of the original data and the number of blocks, the blocks
+// are read here and decoded on up to the given number of threads at the same time
+pub fn verify_blocks<R: Read>(
+	mut reader: R,
+	config: &Config,
+	block_size: u64,
+	threads: usize,
+) -> AnyResult<(R, u64, u64)> {
+	let block_config: Config = block_config(config);
+	// a block waits for a thread only when all of them are busy
+	let (sender, receiver): (SyncSender<PendingBlock>, Receiver<PendingBlock>) =
+		sync_channel(threads);
+	let receiver: Mutex<Receiver<PendingBlock>> = Mutex::new(receiver);
+	let failed: AtomicBool = AtomicBool::new(false);
+	let (original_size, blocks): (u64, u64) = scope(|scope| -> AnyResult<(u64, u64)> {
+		let workers: Vec<ScopedJoinHandle<AnyResult<()>>> = (0..threads.max(1))
+			.map(|_| {
+				scope.spawn(|| {
+					let mut result: AnyResult<()> = Ok(());
+					loop {
+						let next: Option<PendingBlock> = receiver
+							.lock()
+							.unwrap_or_else(|error| error.into_inner())
+							.recv()
+							.ok();
+						let (compressed, original_size): PendingBlock = match next {
+							Some(block) => block,
+							None => return result,
+						};
+						// after a failed block the others are only taken off the channel, so the
+						// reader is never left waiting on a full one
+						if result.is_ok() && !failed.load(Ordering::Relaxed) {
+							result = verify_block(&compressed, original_size, &block_config);
+							failed.fetch_or(result.is_err(), Ordering::Relaxed);
+						}
+					}
+				})
+			})
+			.collect();
+		let read: AnyResult<(u64, u64)> =
+			send_blocks(&mut reader, config, block_size, sender, &failed);
+		// a failed block is why the reading stopped, so its error comes first
+		for worker in workers {
+			match worker.join() {
+				Ok(result) => result?,
+				Err(error) => return Err(AnyError::from_box(error)),
+			}
+		}
+		read
+	})?;
+	copy(&mut reader, &mut sink())?;
+	Ok((reader, original_size, blocks))
+}
+
+// the blocks in order to the threads checking them, until the end marker or a failed block
+fn send_blocks<R: Read>(
+	reader: &mut R,
+	config: &Config,
+	block_size: u64,
+	sender: SyncSender<PendingBlock>,
+	failed: &AtomicBool,
+) -> AnyResult<(u64, u64)> {
+	let mut original_offset: u64 = 0;
+	let mut blocks: u64 = 0;
+	while let Some((header, compressed)) =
+		read_block(reader, config.sync_markers, block_size, original_offset)?
+	{
+		if failed.load(Ordering::Relaxed)
+			|| sender.send((compressed, header.original_size)).is_err()
+		{
+			break;
+		}
+		original_offset = next_offset(original_offset, header.original_size)?;
+		blocks += 1;
+	}
+	Ok((original_offset, blocks))
+}
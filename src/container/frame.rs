@@ -0,0 +1,45 @@
+/*
+ * srx: The fast Symbol Ranking based compressor.
+ * Copyright (C) 2023-2024  Mai Thanh Minh (a.k.a. thanhminhmr)
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU General Public License as published by the Free Software
+ * Foundation, either  version 3 of the  License,  or (at your option) any later
+ * version.
+ *
+ * This program  is distributed in the hope  that it will be useful, but WITHOUT
+ * ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+ * FOR  A PARTICULAR PURPOSE. See  the  GNU  General  Public   License  for more
+ * details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+ */
+
+use super::block::BlockInfo;
+use crate::config::Config;
+
+// -----------------------------------------------
+
+/// What a srx file holds, as found by [`inspect`](crate::inspect) without decompressing it.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct FrameInfo {
+	/// Version of the header.
+	pub version: u8,
+	/// Parameters recorded in the header.
+	pub config: Config,
+	/// Size of the header in bytes.
+	pub header_size: u64,
+	/// Size of the whole file in bytes, header included.
+	pub compressed_size: u64,
+	/// The blocks of a blocked file, none otherwise.
+	pub blocks: Vec<BlockInfo>,
+}
+
+impl FrameInfo {
+	/// Size of the original data, if it was recorded.
+	pub fn original_size(&self) -> Option<u64> {
+		self.config.content_size
+	}
+}
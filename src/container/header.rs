@@ -0,0 +1,190 @@
+/*
+ * srx: The fast Symbol Ranking based compressor.
+ * Copyright (C) 2023-2024  Mai Thanh Minh (a.k.a. thanhminhmr)
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU General Public License as published by the Free Software
+ * Foundation, either  version 3 of the  License,  or (at your option) any later
+ * version.
+ *
+ * This program  is distributed in the hope  that it will be useful, but WITHOUT
+ * ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+ * FOR  A PARTICULAR PURPOSE. See  the  GNU  General  Public   License  for more
+ * details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+ */
+
+use crate::basic::{AnyError, AnyResult};
+use crate::checksum::Checksum;
+use crate::config::{Config, ContextSize};
+use crate::secondary_context::AdaptationProfile;
+use std::io::{Read, Write};
+
+// -----------------------------------------------
+
+// The header starts with the magic and a version byte. Version 0 has nothing else and means the
+// default parameters. Version 1 is followed by a list of fields [tag, length, value...] ended by a
+// zero tag. Files made with the default parameters keep the version 0 header so that older srx
+// can still read them.
+
+const MAGIC: &[u8; 3] = b"sRx";
+
+const VERSION_0: u8 = 0;
+const VERSION_1: u8 = 1;
+
+const TAG_END: u8 = 0;
+const TAG_PROFILE: u8 = 1;
+const TAG_CHECKSUM: u8 = 2;
+const TAG_CONTENT_SIZE: u8 = 3;
+const TAG_CONTEXT_SIZE: u8 = 4;
+const TAG_BLOCK_SIZE: u8 = 5;
+const TAG_SYNC_MARKERS: u8 = 6;
+
+// -----------------------------------------------
+
+/// Whether the bytes start like a srx file, to tell compressed data from the rest. Only the first
+/// three bytes are looked at.
+pub fn is_srx(bytes: &[u8]) -> bool {
+	bytes.starts_with(MAGIC)
+}
+
+// -----------------------------------------------
+
+pub struct Header {
+	pub version: u8,
+	pub config: Config,
+}
+
+impl Header {
+	// the oldest version that can hold the config, with the context size settled. The content size
+	// alone does not make a version 1 header: the data reads back the same without it, only its
+	// size is not checked, and older srx can still read the file.
+	pub fn new(config: Config) -> Self {
+		let mut header: Header = Header {
+			version: VERSION_0,
+			config: Config {
+				context_size: Some(config.resolved_context_size()),
+				content_size: None,
+				..config
+			},
+		};
+		if !header.fields().is_empty() {
+			header.version = VERSION_1;
+			header.config.content_size = config.content_size;
+		}
+		header
+	}
+
+	fn fields(&self) -> Vec<u8> {
+		let mut fields: Vec<u8> = Vec::new();
+		if self.config.profile != AdaptationProfile::default() {
+			fields.extend_from_slice(&[TAG_PROFILE, 1, self.config.profile.id()]);
+		}
+		if self.config.checksum != Checksum::default() {
+			fields.extend_from_slice(&[TAG_CHECKSUM, 1, self.config.checksum.id()]);
+		}
+		if let Some(content_size) = self.config.content_size {
+			fields.extend_from_slice(&[TAG_CONTENT_SIZE, 8]);
+			fields.extend_from_slice(&content_size.to_le_bytes());
+		}
+		if let Some(block_size) = self.config.block_size {
+			fields.extend_from_slice(&[TAG_BLOCK_SIZE, 8]);
+			fields.extend_from_slice(&block_size.to_le_bytes());
+		}
+		if self.config.sync_markers {
+			fields.extend_from_slice(&[TAG_SYNC_MARKERS, 0]);
+		}
+		let context_size: ContextSize = self.config.resolved_context_size();
+		if context_size != ContextSize::MAX {
+			fields.extend_from_slice(&[
+				TAG_CONTEXT_SIZE,
+				2,
+				context_size.primary_bits,
+				context_size.literal_bits,
+			]);
+		}
+		fields
+	}
+
+	pub fn write<W: Write>(&self, writer: &mut W) -> AnyResult<()> {
+		writer.write_all(MAGIC)?;
+		writer.write_all(&[self.version])?;
+		if self.version == VERSION_1 {
+			writer.write_all(&self.fields())?;
+			writer.write_all(&[TAG_END])?;
+		}
+		Ok(())
+	}
+
+	pub fn read<R: Read>(reader: &mut R) -> AnyResult<Self> {
+		let mut buffer: [u8; 4] = [0; 4];
+		reader.read_exact(&mut buffer)?;
+		if !buffer[0..3].eq(MAGIC) {
+			return Err(AnyError::from_string("Not a SRX compressed file!"));
+		}
+		// files without the context size field use the largest one
+		let mut header: Header = Header {
+			version: buffer[3],
+			config: Config {
+				context_size: Some(ContextSize::MAX),
+				..Config::default()
+			},
+		};
+		match header.version {
+			VERSION_0 => {}
+			VERSION_1 => loop {
+				let mut tag: [u8; 1] = [0; 1];
+				reader.read_exact(&mut tag)?;
+				if tag[0] == TAG_END {
+					break;
+				}
+				let mut length: [u8; 1] = [0; 1];
+				reader.read_exact(&mut length)?;
+				let mut value: Vec<u8> = vec![0; length[0] as usize];
+				reader.read_exact(&mut value)?;
+				header.field(tag[0], &value)?;
+			},
+			_ => return Err(AnyError::from_string("Unsupported SRX version!")),
+		}
+		Ok(header)
+	}
+
+	fn field(&mut self, tag: u8, value: &[u8]) -> AnyResult<()> {
+		match (tag, value) {
+			(TAG_PROFILE, &[id]) => {
+				self.config.profile = AdaptationProfile::from_id(id)
+					.ok_or_else(|| AnyError::from_string("Unknown adaptation profile!"))?;
+			}
+			(TAG_CHECKSUM, &[id]) => {
+				self.config.checksum = Checksum::from_id(id)
+					.ok_or_else(|| AnyError::from_string("Unknown checksum!"))?;
+			}
+			(TAG_CONTENT_SIZE, &[a, b, c, d, e, f, g, h]) => {
+				self.config.content_size = Some(u64::from_le_bytes([a, b, c, d, e, f, g, h]));
+			}
+			(TAG_BLOCK_SIZE, &[a, b, c, d, e, f, g, h]) => {
+				let block_size: u64 = u64::from_le_bytes([a, b, c, d, e, f, g, h]);
+				if block_size == 0 {
+					return Err(AnyError::from_string("Unsupported block size!"));
+				}
+				self.config.block_size = Some(block_size);
+			}
+			(TAG_SYNC_MARKERS, &[]) => self.config.sync_markers = true,
+			(TAG_CONTEXT_SIZE, &[primary_bits, literal_bits]) => {
+				let context_size: ContextSize = ContextSize {
+					primary_bits,
+					literal_bits,
+				};
+				if !context_size.is_valid() {
+					return Err(AnyError::from_string("Unsupported context size!"));
+				}
+				self.config.context_size = Some(context_size);
+			}
+			_ => return Err(AnyError::from_string("Unsupported SRX header field!")),
+		}
+		Ok(())
+	}
+}
@@ -0,0 +1,224 @@
+/*
+ * srx: The fast Symbol Ranking based compressor.
+ * Copyright (C) 2023-2024  Mai Thanh Minh (a.k.a. thanhminhmr)
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU General Public License as published by the Free Software
+ * Foundation, either  version 3 of the  License,  or (at your option) any later
+ * version.
+ *
+ * This program  is distributed in the hope  that it will be useful, but WITHOUT
+ * ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+ * FOR  A PARTICULAR PURPOSE. See  the  GNU  General  Public   License  for more
+ * details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+ */
+
+pub use self::block::BlockInfo;
+use self::block::{compress_blocks, decompress_blocks, read_index};
+use self::counter::Counter;
+pub use self::frame::FrameInfo;
+pub use self::header::is_srx;
+use self::header::Header;
+pub use self::parallel::{compress_slice_parallel, compress_slice_parallel_with_threads};
+pub use self::recover::{recover, recover_with, RecoveryReport};
+pub use self::seekable::SrxSeekableReader;
+pub use self::stream::SrxStreamWriter;
+pub use self::verify::{verify, verify_with_threads, VerifyReport};
+use crate::basic::{pipe_tuning, AnyError, AnyResult, PipeTuning};
+use crate::codec::{context_memory_usage, decode_piped, encode_counted, MatchCounter, MatchStats};
+use crate::config::Config;
+use std::io::{sink, Read, Seek, SeekFrom, Sink, Write};
+use std::mem::size_of;
+
+mod block;
+mod counter;
+mod frame;
+mod header;
+mod parallel;
+mod recover;
+mod seekable;
+mod stream;
+mod verify;
+
+#[cfg(test)]
+mod test;
+
+// -----------------------------------------------
+
+// block size used by the writers that choose it, large enough to keep most of the ratio
+const DEFAULT_BLOCK_SIZE: u64 = 0x400000;
+
+// -----------------------------------------------
+
+// the codec with the buffer sizes of the pipe tuning, each pair of sizes is an instance of its own:
+// the six encoders and three decoders (again for verifying, into a sink) take about 0.7 MiB of a
+// 4 MiB release build, the price of letting srx tune pick the sizes without a rebuild
+fn encode_tuned<R: Read + Send, W: Write + Send, C: MatchCounter>(
+	reader: R,
+	writer: W,
+	config: &Config,
+	counter: C,
+	tuning: &PipeTuning,
+) -> AnyResult<(R, W, C)> {
+	let depth: usize = tuning.pipe_depth;
+	match (tuning.io_buffer_size, tuning.message_buffer_size) {
+		(0x40000, 0x10000) => {
+			encode_counted::<R, W, C, 0x40000, 0x10000>(reader, writer, config, counter, depth)
+		}
+		(0x40000, _) => {
+			encode_counted::<R, W, C, 0x40000, 0x40000>(reader, writer, config, counter, depth)
+		}
+		(0x100000, 0x10000) => {
+			encode_counted::<R, W, C, 0x100000, 0x10000>(reader, writer, config, counter, depth)
+		}
+		(0x100000, _) => {
+			encode_counted::<R, W, C, 0x100000, 0x40000>(reader, writer, config, counter, depth)
+		}
+		(_, 0x10000) => {
+			encode_counted::<R, W, C, 0x400000, 0x10000>(reader, writer, config, counter, depth)
+		}
+		_ => encode_counted::<R, W, C, 0x400000, 0x40000>(reader, writer, config, counter, depth),
+	}
+}
+
+// decode only to count and check the data, through the same pipes to a writer that keeps nothing
+fn verify_tuned<R: Read + Send>(
+	reader: R,
+	config: &Config,
+	tuning: &PipeTuning,
+) -> AnyResult<(R, u64)> {
+	let (reader, writer): (R, Counter<Sink>) =
+		decode_tuned(reader, Counter::new(sink()), config, tuning)?;
+	Ok((reader, writer.count()))
+}
+
+fn decode_tuned<R: Read + Send, W: Write + Send>(
+	reader: R,
+	writer: W,
+	config: &Config,
+	tuning: &PipeTuning,
+) -> AnyResult<(R, W)> {
+	let depth: usize = tuning.pipe_depth;
+	match tuning.io_buffer_size {
+		0x40000 => decode_piped::<R, W, 0x40000>(reader, writer, config, depth),
+		0x100000 => decode_piped::<R, W, 0x100000>(reader, writer, config, depth),
+		_ => decode_piped::<R, W, 0x400000>(reader, writer, config, depth),
+	}
+}
+
+// -----------------------------------------------
+
+/// Approximate memory taken by one compression or decompression with this config, the context
+/// tables shrink with the known or expected size of the data.
+pub fn memory_usage(config: &Config) -> usize {
+	// depth + 1 buffers in each of the reader and the writer pipes and in the message pipe
+	// (messages are packed into u32), and a block with its coded data when blocked
+	let tuning: PipeTuning = pipe_tuning();
+	let block_size: u64 = config.block_size.map_or(0, |block_size| {
+		block_size.min(config.content_size.unwrap_or(block_size))
+	});
+	// a block larger than the address space saturates, it cannot be held anyway
+	let block_size: usize = usize::try_from(block_size).unwrap_or(usize::MAX);
+	(context_memory_usage(config)
+		+ (tuning.pipe_depth + 1)
+			* (2 * tuning.io_buffer_size + tuning.message_buffer_size * size_of::<u32>()))
+	.saturating_add(block_size.saturating_mul(2))
+}
+
+// a size recorded in the header must match what went through the codec
+fn check_content_size(config: &Config, size: u64) -> AnyResult<()> {
+	match config.content_size {
+		Some(content_size) if content_size != size => Err(AnyError::from_string(
+			"Data size does not match the recorded content size!",
+		)),
+		_ => Ok(()),
+	}
+}
+
+/// Compress everything from the reader into a srx file written to the writer.
+pub fn compress<R: Read + Send, W: Write + Send>(
+	reader: R,
+	writer: W,
+	config: &Config,
+) -> AnyResult<(R, W)> {
+	let (reader, writer, _): (R, W, ()) = compress_counted(reader, writer, config, ())?;
+	Ok((reader, writer))
+}
+
+/// Compress like [`compress`], also counting how the bytes matched their contexts.
+pub fn compress_with_stats<R: Read + Send, W: Write + Send>(
+	reader: R,
+	writer: W,
+	config: &Config,
+) -> AnyResult<(R, W, MatchStats)> {
+	compress_counted(reader, writer, config, MatchStats::default())
+}
+
+fn compress_counted<R: Read + Send, W: Write + Send, C: MatchCounter>(
+	reader: R,
+	writer: W,
+	config: &Config,
+	counter: C,
+) -> AnyResult<(R, W, C)> {
+	if config.sync_markers && config.block_size.is_none() {
+		return Err(AnyError::from_string("Sync markers need a block size!"));
+	}
+	let header: Header = Header::new(config.clone());
+	let mut writer: Counter<W> = Counter::new(writer);
+	header.write(&mut writer)?;
+	let (reader, writer, counter): (Counter<R>, Counter<W>, C) = match header.config.block_size {
+		None => encode_tuned(
+			Counter::new(reader),
+			writer,
+			&header.config,
+			counter,
+			&pipe_tuning(),
+		)?,
+		Some(block_size) => compress_blocks(
+			Counter::new(reader),
+			writer,
+			&header.config,
+			block_size,
+			counter,
+		)?,
+	};
+	// the size given is checked even when the header has no room for it
+	check_content_size(config, reader.count())?;
+	Ok((reader.into_inner(), writer.into_inner(), counter))
+}
+
+/// Decompress a srx file from the reader, the parameters are taken from its header.
+pub fn decompress<R: Read + Send, W: Write + Send>(mut reader: R, writer: W) -> AnyResult<(R, W)> {
+	let header: Header = Header::read(&mut reader)?;
+	let (reader, writer): (R, Counter<W>) = match header.config.block_size {
+		None => decode_tuned(reader, Counter::new(writer), &header.config, &pipe_tuning())?,
+		Some(block_size) => {
+			decompress_blocks(reader, Counter::new(writer), &header.config, block_size)?
+		}
+	};
+	check_content_size(&header.config, writer.count())?;
+	Ok((reader, writer.into_inner()))
+}
+
+/// Read the header of a srx file and the index of its blocks, and measure it, without decompressing.
+/// Only the header and the index are read, the rest is skipped by seeking.
+pub fn inspect<R: Read + Seek>(mut reader: R) -> AnyResult<FrameInfo> {
+	let start: u64 = reader.stream_position()?;
+	let header: Header = Header::read(&mut reader)?;
+	let header_size: u64 = reader.stream_position()? - start;
+	let blocks: Vec<BlockInfo> = match header.config.block_size {
+		None => Vec::new(),
+		Some(block_size) => read_index(&mut reader, block_size)?,
+	};
+	Ok(FrameInfo {
+		version: header.version,
+		config: header.config,
+		header_size,
+		compressed_size: reader.seek(SeekFrom::End(0))? - start,
+		blocks,
+	})
+}
@@ -0,0 +1,131 @@
+/*
+ * srx: The fast Symbol Ranking based compressor.
+ * Copyright (C) 2023-2024  Mai Thanh Minh (a.k.a. thanhminhmr)
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU General Public License as published by the Free Software
+ * Foundation, either  version 3 of the  License,  or (at your option) any later
+ * version.
+ *
+ * This program  is distributed in the hope  that it will be useful, but WITHOUT
+ * ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+ * FOR  A PARTICULAR PURPOSE. See  the  GNU  General  Public   License  for more
+ * details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+ */
+
+use super::block::{block_config, encode_block, write_block, write_index, BlockInfo};
+use super::counter::Counter;
+use super::header::Header;
+use super::DEFAULT_BLOCK_SIZE;
+use crate::basic::{AnyError, AnyResult};
+use crate::config::Config;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::thread::{available_parallelism, scope, ScopedJoinHandle};
+
+// -----------------------------------------------
+
+// the coded blocks of a worker, with their index
+type CodedBlocks = Vec<(usize, Vec<u8>)>;
+
+// -----------------------------------------------
+
+/// Compress a buffer held in memory into a blocked srx file, coding the blocks on as many threads as
+/// there are cores (see [`compress_slice_parallel_with_threads`]).
+///
+/// An error is returned for a block size of zero or one larger than the address space, and if a
+/// codec thread fails.
+pub fn compress_slice_parallel(data: &[u8], config: &Config) -> AnyResult<Vec<u8>> {
+	let threads: usize = available_parallelism().map_or(1, |threads| threads.get());
+	compress_slice_parallel_with_threads(data, config, threads)
+}
+
+/// Compress a buffer held in memory into a blocked srx file, coding the blocks on up to the given
+/// number of threads. Each of them runs a whole codec, with the memory told by [`memory_usage`]
+/// for a block, so the count should fit the memory as well as the cores. The block size of the
+/// config is used, 4 MiB if it has none. The content size is always recorded.
+///
+/// An error is returned for a block size of zero or one larger than the address space, for zero
+/// threads, and if a codec thread fails.
+///
+/// [`memory_usage`]: crate::memory_usage
+pub fn compress_slice_parallel_with_threads(
+	data: &[u8],
+	config: &Config,
+	threads: usize,
+) -> AnyResult<Vec<u8>> {
+	let block_size: u64 = config.block_size.unwrap_or(DEFAULT_BLOCK_SIZE);
+	if block_size == 0 || block_size > usize::MAX as u64 {
+		return Err(AnyError::from_string("Unsupported block size!"));
+	}
+	if threads == 0 {
+		return Err(AnyError::from_string("Unsupported number of threads!"));
+	}
+	let header: Header = Header::new(Config {
+		content_size: Some(data.len() as u64),
+		block_size: Some(block_size),
+		..config.clone()
+	});
+	let block_config: Config = block_config(&header.config);
+	let chunks: Vec<&[u8]> = data.chunks(block_size as usize).collect();
+
+	// every worker takes the next block until none is left or one of them failed, the blocks are
+	// put back in order after
+	let next: AtomicUsize = AtomicUsize::new(0);
+	let failed: AtomicBool = AtomicBool::new(false);
+	let threads: usize = threads.min(chunks.len()).max(1);
+	let mut compressed: Vec<Vec<u8>> = vec![Vec::new(); chunks.len()];
+	scope(|scope| -> AnyResult<()> {
+		let workers: Vec<ScopedJoinHandle<AnyResult<CodedBlocks>>> = (0..threads)
+			.map(|_| {
+				scope.spawn(|| {
+					let mut done: CodedBlocks = Vec::new();
+					while !failed.load(Ordering::Relaxed) {
+						let index: usize = next.fetch_add(1, Ordering::Relaxed);
+						match chunks
+							.get(index)
+							.map(|chunk| encode_block(chunk, &block_config, ()))
+						{
+							Some(Ok((compressed, ()))) => done.push((index, compressed)),
+							Some(Err(error)) => {
+								failed.store(true, Ordering::Relaxed);
+								return Err(error);
+							}
+							None => break,
+						}
+					}
+					Ok(done)
+				})
+			})
+			.collect();
+		for worker in workers {
+			let done: CodedBlocks = match worker.join() {
+				Ok(done) => done?,
+				Err(error) => return Err(AnyError::from_box(error)),
+			};
+			for (index, block) in done {
+				compressed[index] = block;
+			}
+		}
+		Ok(())
+	})?;
+
+	let total: usize = compressed.iter().map(|block| block.len()).sum();
+	let mut writer: Counter<Vec<u8>> = Counter::new(Vec::with_capacity(total + 0x100));
+	header.write(&mut writer)?;
+	let mut blocks: Vec<BlockInfo> = Vec::with_capacity(chunks.len());
+	for (chunk, block) in chunks.iter().zip(&compressed) {
+		write_block(
+			&mut writer,
+			chunk.len() as u64,
+			block,
+			&mut blocks,
+			header.config.sync_markers,
+		)?;
+	}
+	write_index(&mut writer, &blocks, header.config.sync_markers)?;
+	Ok(writer.into_inner())
+}
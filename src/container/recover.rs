@@ -0,0 +1,178 @@
+/*
+ * srx: The fast Symbol Ranking based compressor.
+ * Copyright (C) 2023-2024  Mai Thanh Minh (a.k.a. thanhminhmr)
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU General Public License as published by the Free Software
+ * Foundation, either  version 3 of the  License,  or (at your option) any later
+ * version.
+ *
+ * This program  is distributed in the hope  that it will be useful, but WITHOUT
+ * ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+ * FOR  A PARTICULAR PURPOSE. See  the  GNU  General  Public   License  for more
+ * details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+ */
+
+use super::block::{
+	block_config, decode_block, in_memory_size, BlockHeader, SYNC_HEADER_SIZE, SYNC_MAGIC,
+};
+use super::header::Header;
+use crate::basic::{AnyError, AnyResult};
+use crate::config::Config;
+use std::io::{Read, Write};
+use std::ops::Range;
+
+// -----------------------------------------------
+
+// how much more is read each time no marker is found
+const SCAN_SIZE: usize = 0x10000;
+
+// -----------------------------------------------
+
+/// What [`recover`] found in a srx file.
+#[derive(Clone, Eq, PartialEq, Debug, Default)]
+pub struct RecoveryReport {
+	/// Number of blocks decoded.
+	pub blocks: u64,
+	/// Size of the data written out.
+	pub recovered_size: u64,
+	/// Ranges of the original data that could not be recovered, in order.
+	pub lost: Vec<Range<u64>>,
+	/// Size of the original data, from the header or the end marker. When it is not known, data
+	/// may be missing after the last block.
+	pub original_size: Option<u64>,
+}
+
+// -----------------------------------------------
+
+// a window over the stream that moves forward from marker to marker
+struct Scanner<R: Read> {
+	reader: R,
+	window: Vec<u8>,
+}
+
+impl<R: Read> Scanner<R> {
+	// make the window at least this long, false if the stream ends before
+	fn fill(&mut self, length: usize) -> AnyResult<bool> {
+		if self.window.len() < length {
+			let missing: u64 = (length - self.window.len()) as u64;
+			self.reader
+				.by_ref()
+				.take(missing)
+				.read_to_end(&mut self.window)?;
+		}
+		Ok(self.window.len() >= length)
+	}
+
+	fn consume(&mut self, length: usize) {
+		self.window.drain(..length);
+	}
+
+	// move the window to the next sync marker, false if the stream ends before
+	fn find_marker(&mut self) -> AnyResult<bool> {
+		loop {
+			if let Some(position) = self
+				.window
+				.windows(SYNC_MAGIC.len())
+				.position(|bytes| bytes.eq(SYNC_MAGIC))
+			{
+				self.consume(position);
+				return Ok(true);
+			}
+			// keep what may be the start of a marker cut by the end of the window
+			let kept: usize = self.window.len().min(SYNC_MAGIC.len() - 1);
+			self.consume(self.window.len() - kept);
+			let length: usize = self.window.len();
+			self.fill(length + SCAN_SIZE)?;
+			if self.window.len() == length {
+				return Ok(false);
+			}
+		}
+	}
+}
+
+// -----------------------------------------------
+
+/// Decompress what can be found in a srx file with sync markers (see [`Config::sync_markers`]),
+/// skipping damaged blocks instead of failing on them.
+pub fn recover<R: Read, W: Write>(mut reader: R, writer: W) -> AnyResult<(R, W, RecoveryReport)> {
+	let header: Header = Header::read(&mut reader)?;
+	recover_with(reader, writer, &header.config)
+}
+
+/// Like [`recover`], for a reader that does not start at the header, like one that was opened in
+/// the middle of a file that is still growing. The config must be the one from the header, and
+/// everything before the first block found is reported as lost.
+pub fn recover_with<R: Read, W: Write>(
+	reader: R,
+	mut writer: W,
+	config: &Config,
+) -> AnyResult<(R, W, RecoveryReport)> {
+	let block_size: u64 = match config.block_size {
+		Some(block_size) if config.sync_markers => block_size,
+		_ => return Err(AnyError::from_string("No sync markers to recover from!")),
+	};
+	let block_config: Config = block_config(config);
+	let mut scanner: Scanner<R> = Scanner {
+		reader,
+		window: Vec::new(),
+	};
+	let mut report: RecoveryReport = RecoveryReport::default();
+	let mut expected_offset: u64 = 0;
+	report.original_size = config.content_size;
+	while scanner.find_marker()? && scanner.fill(SYNC_HEADER_SIZE)? {
+		// a marker with a broken header is just some bytes that look like a marker
+		let header: BlockHeader = match BlockHeader::from_sync_bytes(&scanner.window) {
+			Some(header)
+				if header.original_size <= block_size
+					&& header.compressed_size
+						<= block_size
+							.saturating_mul(2)
+							.saturating_add(SCAN_SIZE as u64) =>
+			{
+				header
+			}
+			_ => {
+				scanner.consume(1);
+				continue;
+			}
+		};
+		if header.original_size == 0 {
+			report.original_size = Some(header.original_offset);
+			break;
+		}
+		let frame_size: usize = SYNC_HEADER_SIZE + in_memory_size(header.compressed_size)?;
+		if !scanner.fill(frame_size)? {
+			break;
+		}
+		let compressed: &[u8] = &scanner.window[SYNC_HEADER_SIZE..frame_size];
+		if crc32fast::hash(compressed) != header.data_crc {
+			scanner.consume(1);
+			continue;
+		}
+		// a block that does not decode is lost, it shows up as a gap before the next one
+		if header.original_offset >= expected_offset {
+			if let Ok(data) = decode_block(compressed, header.original_size, &block_config) {
+				if header.original_offset > expected_offset {
+					report.lost.push(expected_offset..header.original_offset);
+				}
+				writer.write_all(&data)?;
+				report.blocks += 1;
+				report.recovered_size += header.original_size;
+				expected_offset = header.original_offset.saturating_add(header.original_size);
+			}
+		}
+		scanner.consume(frame_size);
+	}
+	if let Some(original_size) = report
+		.original_size
+		.filter(|&original_size| original_size > expected_offset)
+	{
+		report.lost.push(expected_offset..original_size);
+	}
+	Ok((scanner.reader, writer, report))
+}
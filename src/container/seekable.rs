@@ -0,0 +1,167 @@
+/*
+ * srx: The fast Symbol Ranking based compressor.
+ * Copyright (C) 2023-2024  Mai Thanh Minh (a.k.a. thanhminhmr)
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU General Public License as published by the Free Software
+ * Foundation, either  version 3 of the  License,  or (at your option) any later
+ * version.
+ *
+ * This program  is distributed in the hope  that it will be useful, but WITHOUT
+ * ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+ * FOR  A PARTICULAR PURPOSE. See  the  GNU  General  Public   License  for more
+ * details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+ */
+
+use super::block::{block_config, decode_block, read_block_at, read_index, BlockInfo};
+use super::header::Header;
+use crate::basic::{AnyError, AnyResult};
+use crate::config::Config;
+use std::io::{Error, ErrorKind, Read, Result, Seek, SeekFrom};
+
+// -----------------------------------------------
+
+const DEFAULT_CACHE_LIMIT: usize = 4;
+
+// -----------------------------------------------
+
+/// A reader over the original data of a blocked srx file (see [`Config::block_size`]).
+///
+/// Only the blocks that are read from get decompressed, the last few of them are kept in memory,
+/// so a huge compressed file can be read at random places like an ordinary file.
+pub struct SrxSeekableReader<R: Read + Seek> {
+	reader: R,
+	// where the srx file starts in the reader
+	base: u64,
+	config: Config,
+	sync_markers: bool,
+	block_size: u64,
+	blocks: Vec<BlockInfo>,
+	length: u64,
+	position: u64,
+	// decoded blocks, the most recently used first
+	cache: Vec<(usize, Vec<u8>)>,
+	cache_limit: usize,
+}
+
+impl<R: Read + Seek> SrxSeekableReader<R> {
+	/// Open a blocked srx file, its header and block index are read right away. The file starts at
+	/// the current position of the reader and runs to its end, like for [`inspect`](crate::inspect).
+	pub fn new(mut reader: R) -> AnyResult<Self> {
+		let base: u64 = reader.stream_position()?;
+		let header: Header = Header::read(&mut reader)?;
+		let block_size: u64 = header.config.block_size.ok_or_else(|| {
+			AnyError::from_string("Not a blocked SRX file, it cannot be read at random places!")
+		})?;
+		let blocks: Vec<BlockInfo> = read_index(&mut reader, block_size)?;
+		let length: u64 = blocks
+			.last()
+			.map_or(0, |block| block.original_offset + block.original_size);
+		Ok(Self {
+			reader,
+			base,
+			config: block_config(&header.config),
+			sync_markers: header.config.sync_markers,
+			block_size,
+			blocks,
+			length,
+			position: 0,
+			cache: Vec::new(),
+			cache_limit: DEFAULT_CACHE_LIMIT,
+		})
+	}
+
+	/// Size of the original data.
+	pub fn len(&self) -> u64 {
+		self.length
+	}
+
+	/// Whether the original data is empty.
+	pub fn is_empty(&self) -> bool {
+		self.length == 0
+	}
+
+	/// The blocks of the file.
+	pub fn blocks(&self) -> &[BlockInfo] {
+		&self.blocks
+	}
+
+	/// How many decoded blocks are kept in memory, at least one.
+	pub fn set_cache_limit(&mut self, blocks: usize) {
+		self.cache_limit = blocks.max(1);
+		self.cache.truncate(self.cache_limit);
+	}
+
+	/// Give back the underlying reader.
+	pub fn into_inner(self) -> R {
+		self.reader
+	}
+
+	// the decoded block, from the cache or from the file
+	fn block(&mut self, index: usize) -> AnyResult<&[u8]> {
+		match self.cache.iter().position(|(cached, _)| *cached == index) {
+			Some(position) => {
+				let entry: (usize, Vec<u8>) = self.cache.remove(position);
+				self.cache.insert(0, entry);
+			}
+			None => {
+				let block: BlockInfo = self.blocks[index];
+				let compressed: Vec<u8> = read_block_at(
+					&mut self.reader,
+					self.base,
+					&block,
+					self.sync_markers,
+					self.block_size,
+				)?;
+				let data: Vec<u8> = decode_block(&compressed, block.original_size, &self.config)?;
+				self.cache.truncate(self.cache_limit - 1);
+				self.cache.insert(0, (index, data));
+			}
+		}
+		Ok(&self.cache[0].1)
+	}
+}
+
+impl<R: Read + Seek> Read for SrxSeekableReader<R> {
+	fn read(&mut self, buffer: &mut [u8]) -> Result<usize> {
+		if self.position >= self.length || buffer.is_empty() {
+			return Ok(0);
+		}
+		let position: u64 = self.position;
+		let index: usize = self
+			.blocks
+			.partition_point(|block| block.original_offset + block.original_size <= position);
+		let start: usize = (position - self.blocks[index].original_offset) as usize;
+		let data: &[u8] = self
+			.block(index)
+			.map_err(|error| Error::new(ErrorKind::InvalidData, error.to_string()))?;
+		let length: usize = buffer.len().min(data.len() - start);
+		buffer[..length].copy_from_slice(&data[start..start + length]);
+		self.position += length as u64;
+		Ok(length)
+	}
+}
+
+impl<R: Read + Seek> Seek for SrxSeekableReader<R> {
+	fn seek(&mut self, position: SeekFrom) -> Result<u64> {
+		let (base, offset): (u64, i64) = match position {
+			SeekFrom::Start(offset) => (offset, 0),
+			SeekFrom::End(offset) => (self.length, offset),
+			SeekFrom::Current(offset) => (self.position, offset),
+		};
+		match base.checked_add_signed(offset) {
+			Some(position) => {
+				self.position = position;
+				Ok(position)
+			}
+			None => Err(Error::new(
+				ErrorKind::InvalidInput,
+				"Invalid seek to a negative or overflowing position!",
+			)),
+		}
+	}
+}
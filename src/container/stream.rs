@@ -0,0 +1,118 @@
+/*
+ * srx: The fast Symbol Ranking based compressor.
+ * Copyright (C) 2023-2024  Mai Thanh Minh (a.k.a. thanhminhmr)
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU General Public License as published by the Free Software
+ * Foundation, either  version 3 of the  License,  or (at your option) any later
+ * version.
+ *
+ * This program  is distributed in the hope  that it will be useful, but WITHOUT
+ * ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+ * FOR  A PARTICULAR PURPOSE. See  the  GNU  General  Public   License  for more
+ * details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+ */
+
+use super::block::{block_config, encode_block, write_block, write_index, BlockInfo};
+use super::counter::Counter;
+use super::header::Header;
+use super::{check_content_size, DEFAULT_BLOCK_SIZE};
+use crate::basic::{AnyError, AnyResult};
+use crate::config::Config;
+use std::io::{Error, Result, Write};
+
+// -----------------------------------------------
+
+/// A writer compressing into a blocked srx file (see [`Config::block_size`]) as the data comes.
+///
+/// The data is coded a block at a time. [`flush`](Write::flush) codes what is pending as a block of
+/// its own and flushes the underlying writer, so that the other side can decompress everything
+/// written so far, at the cost of some ratio for each short block. The file is only complete after
+/// [`finish`](Self::finish).
+pub struct SrxStreamWriter<W: Write> {
+	writer: Counter<W>,
+	config: Config,
+	block_config: Config,
+	block_size: usize,
+	data: Vec<u8>,
+	blocks: Vec<BlockInfo>,
+}
+
+impl<W: Write> SrxStreamWriter<W> {
+	/// Start a blocked srx file with this config, in blocks of 4 MiB if it has no block size. The
+	/// header is written right away.
+	pub fn new(writer: W, config: &Config) -> AnyResult<Self> {
+		let block_size: u64 = config.block_size.unwrap_or(DEFAULT_BLOCK_SIZE);
+		if block_size == 0 || block_size > usize::MAX as u64 {
+			return Err(AnyError::from_string("Unsupported block size!"));
+		}
+		let header: Header = Header::new(Config {
+			block_size: Some(block_size),
+			..config.clone()
+		});
+		let mut writer: Counter<W> = Counter::new(writer);
+		header.write(&mut writer)?;
+		Ok(Self {
+			writer,
+			block_config: block_config(&header.config),
+			config: header.config,
+			block_size: block_size as usize,
+			data: Vec::new(),
+			blocks: Vec::new(),
+		})
+	}
+
+	// code the pending data as a block
+	fn write_pending(&mut self) -> AnyResult<()> {
+		let (compressed, _): (Vec<u8>, ()) = encode_block(&self.data, &self.block_config, ())?;
+		write_block(
+			&mut self.writer,
+			self.data.len() as u64,
+			&compressed,
+			&mut self.blocks,
+			self.config.sync_markers,
+		)?;
+		self.data.clear();
+		Ok(())
+	}
+
+	/// Code what is pending, end the file with the index of its blocks and give back the underlying
+	/// writer.
+	pub fn finish(mut self) -> AnyResult<W> {
+		if !self.data.is_empty() {
+			self.write_pending()?;
+		}
+		write_index(&mut self.writer, &self.blocks, self.config.sync_markers)?;
+		let size: u64 = self
+			.blocks
+			.last()
+			.map_or(0, |block| block.original_offset + block.original_size);
+		check_content_size(&self.config, size)?;
+		self.writer.flush()?;
+		Ok(self.writer.into_inner())
+	}
+}
+
+impl<W: Write> Write for SrxStreamWriter<W> {
+	fn write(&mut self, buffer: &[u8]) -> Result<usize> {
+		let length: usize = buffer.len().min(self.block_size - self.data.len());
+		self.data.extend_from_slice(&buffer[..length]);
+		if self.data.len() == self.block_size {
+			self.write_pending()
+				.map_err(|error| Error::other(error.to_string()))?;
+		}
+		Ok(length)
+	}
+
+	fn flush(&mut self) -> Result<()> {
+		if !self.data.is_empty() {
+			self.write_pending()
+				.map_err(|error| Error::other(error.to_string()))?;
+		}
+		self.writer.flush()
+	}
+}
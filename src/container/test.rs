@@ -0,0 +1,554 @@
+/*
+ * srx: The fast Symbol Ranking based compressor.
+ * Copyright (C) 2023-2024  Mai Thanh Minh (a.k.a. thanhminhmr)
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU General Public License as published by the Free Software
+ * Foundation, either  version 3 of the  License,  or (at your option) any later
+ * version.
+ *
+ * This program  is distributed in the hope  that it will be useful, but WITHOUT
+ * ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+ * FOR  A PARTICULAR PURPOSE. See  the  GNU  General  Public   License  for more
+ * details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+ */
+
+use super::header::Header;
+use super::{
+	compress, compress_slice_parallel, compress_slice_parallel_with_threads, compress_with_stats,
+	decode_tuned, decompress, encode_tuned, inspect, is_srx, recover, recover_with, verify,
+	verify_tuned, verify_with_threads, BlockInfo, FrameInfo, RecoveryReport, SrxSeekableReader,
+	SrxStreamWriter, VerifyReport,
+};
+use crate::basic::{set_pipe_tuning, AnyResult, PipeTuning};
+use crate::checksum::Checksum;
+use crate::codec::MatchStats;
+use crate::config::{Config, ContextSize};
+use std::io::{Cursor, Read, Seek, SeekFrom, Write};
+use std::ops::Range;
+
+// -----------------------------------------------
+
+#[test]
+fn test_inspect_and_content_size() -> AnyResult<()> {
+	let data: Vec<u8> = b"peter piper picked a peck of pickled peppers; ".repeat(64);
+	let config: Config = Config {
+		checksum: Checksum::Crc32,
+		content_size: Some(data.len() as u64),
+		..Config::default()
+	};
+	let (_, compressed): (Cursor<&[u8]>, Vec<u8>) =
+		compress(Cursor::new(data.as_slice()), Vec::new(), &config)?;
+
+	// the header gives back the config, and the sizes add up without decompressing
+	let info: FrameInfo = inspect(Cursor::new(&compressed))?;
+	assert_eq!(info.version, 1);
+	assert_eq!(
+		info.config,
+		Config {
+			context_size: Some(ContextSize::for_size(data.len() as u64)),
+			..config.clone()
+		}
+	);
+	assert_eq!(info.original_size(), Some(data.len() as u64));
+	assert_eq!(info.compressed_size, compressed.len() as u64);
+	assert!(info.header_size < info.compressed_size);
+
+	let (_, decompressed): (Cursor<&[u8]>, Vec<u8>) =
+		decompress(Cursor::new(compressed.as_slice()), Vec::new())?;
+	assert_eq!(decompressed, data);
+
+	// a wrong size is caught while compressing
+	let wrong_config: Config = Config {
+		content_size: Some(data.len() as u64 + 1),
+		..config
+	};
+	assert!(compress(Cursor::new(data.as_slice()), Vec::new(), &wrong_config).is_err());
+	Ok(())
+}
+
+#[test]
+fn test_header_versions() -> AnyResult<()> {
+	let header = |content_size: u64| -> AnyResult<Vec<u8>> {
+		let config: Config = Config {
+			content_size: Some(content_size),
+			..Config::default()
+		};
+		let mut bytes: Vec<u8> = Vec::new();
+		Header::new(config).write(&mut bytes)?;
+		Ok(bytes)
+	};
+
+	// a small file gets smaller tables, recorded with its size
+	let small: Vec<u8> = header(0x1000)?;
+	assert_eq!(small[..4], *b"sRx\x01");
+	let header_size: u64 = small.len() as u64;
+	let info: FrameInfo = inspect(Cursor::new(small))?;
+	assert_eq!(info.original_size(), Some(0x1000));
+	assert_eq!(info.header_size, header_size);
+
+	// with the tables that older srx assume, the size alone does not change the header
+	if ContextSize::DEFAULT == ContextSize::MAX {
+		assert_eq!(header(1 << 30)?, b"sRx\x00");
+	}
+
+	// the size is checked all the same
+	let config: Config = Config {
+		content_size: Some(1 << 30),
+		..Config::default()
+	};
+	assert!(compress(Cursor::new(&b"not that much"[..]), Vec::new(), &config).is_err());
+	Ok(())
+}
+
+#[test]
+fn test_seekable_reader() -> AnyResult<()> {
+	let data: Vec<u8> = (0..50000_u32)
+		.flat_map(|index| (index * 7 / 3).to_le_bytes())
+		.collect();
+	let config: Config = Config {
+		block_size: Some(0x4000),
+		..Config::default()
+	};
+	let (_, compressed): (Cursor<&[u8]>, Vec<u8>) =
+		compress(Cursor::new(data.as_slice()), Vec::new(), &config)?;
+
+	// blocked files still decompress in one go
+	let (_, decompressed): (Cursor<&[u8]>, Vec<u8>) =
+		decompress(Cursor::new(compressed.as_slice()), Vec::new())?;
+	assert_eq!(decompressed, data);
+	let info: FrameInfo = inspect(Cursor::new(&compressed))?;
+	assert_eq!(info.blocks.len(), data.len().div_ceil(0x4000));
+	assert_eq!(info.compressed_size, compressed.len() as u64);
+
+	// only the header and the index are read, the blocks between them are skipped
+	let mut wiped: Vec<u8> = compressed.clone();
+	let first: u64 = info.blocks[0].offset;
+	let last: &BlockInfo = info.blocks.last().unwrap();
+	wiped[first as usize..(last.offset + last.compressed_size) as usize].fill(0);
+	assert_eq!(inspect(Cursor::new(&wiped))?, info);
+
+	let mut reader: SrxSeekableReader<Cursor<&[u8]>> =
+		SrxSeekableReader::new(Cursor::new(compressed.as_slice()))?;
+	reader.set_cache_limit(2);
+	assert_eq!(reader.len(), data.len() as u64);
+	assert_eq!(reader.blocks(), info.blocks.as_slice());
+
+	// reads across block boundaries, backward and from the end
+	for (position, length) in [(0x3FF0, 0x20), (100, 10), (150000, 0x9000), (0, 1)] {
+		let mut buffer: Vec<u8> = vec![0; length];
+		reader.seek(SeekFrom::Start(position))?;
+		reader.read_exact(&mut buffer)?;
+		assert_eq!(buffer, data[position as usize..position as usize + length]);
+	}
+	let mut tail: Vec<u8> = Vec::new();
+	reader.seek(SeekFrom::End(-5))?;
+	reader.read_to_end(&mut tail)?;
+	assert_eq!(tail, data[data.len() - 5..]);
+	assert!(reader
+		.seek(SeekFrom::Current(-(data.len() as i64) - 1))
+		.is_err());
+
+	// a file embedded after other data is read from where the reader is, like inspect does
+	let mut embedded: Vec<u8> = b"some other data".to_vec();
+	embedded.extend_from_slice(&compressed);
+	let mut cursor: Cursor<&[u8]> = Cursor::new(embedded.as_slice());
+	cursor.seek(SeekFrom::Start(15))?;
+	assert_eq!(inspect(&mut cursor)?, info);
+	cursor.seek(SeekFrom::Start(15))?;
+	let mut reader: SrxSeekableReader<Cursor<&[u8]>> = SrxSeekableReader::new(cursor)?;
+	let mut whole: Vec<u8> = Vec::new();
+	reader.read_to_end(&mut whole)?;
+	assert_eq!(whole, data);
+
+	// unblocked files cannot be read at random places
+	let (_, unblocked): (Cursor<&[u8]>, Vec<u8>) =
+		compress(Cursor::new(data.as_slice()), Vec::new(), &Config::default())?;
+	assert!(SrxSeekableReader::new(Cursor::new(unblocked.as_slice())).is_err());
+	Ok(())
+}
+
+#[test]
+fn test_recover_from_sync_markers() -> AnyResult<()> {
+	let data: Vec<u8> = b"she sells sea shells by the sea shore; ".repeat(1024);
+	let config: Config = Config {
+		block_size: Some(0x1000),
+		sync_markers: true,
+		content_size: Some(data.len() as u64),
+		..Config::default()
+	};
+	let (_, mut compressed): (Cursor<&[u8]>, Vec<u8>) =
+		compress(Cursor::new(data.as_slice()), Vec::new(), &config)?;
+	let (_, recovered, report): (Cursor<&[u8]>, Vec<u8>, RecoveryReport) =
+		recover(Cursor::new(compressed.as_slice()), Vec::new())?;
+	assert_eq!(recovered, data);
+	assert!(report.lost.is_empty());
+	assert_eq!(report.original_size, Some(data.len() as u64));
+
+	// damage the third block, the others are still there
+	let info: FrameInfo = inspect(Cursor::new(&compressed))?;
+	let block: BlockInfo = info.blocks[2];
+	compressed[(block.offset + 50) as usize] ^= 0xFF;
+	assert!(decompress(Cursor::new(compressed.as_slice()), Vec::new()).is_err());
+	let (_, recovered, report): (Cursor<&[u8]>, Vec<u8>, RecoveryReport) =
+		recover(Cursor::new(compressed.as_slice()), Vec::new())?;
+	let lost: Range<u64> = block.original_offset..block.original_offset + block.original_size;
+	assert_eq!(report.lost, vec![lost.clone()]);
+	assert_eq!(
+		recovered[..lost.start as usize],
+		data[..lost.start as usize]
+	);
+	assert_eq!(recovered[lost.start as usize..], data[lost.end as usize..]);
+
+	// start in the middle of the fifth block, as a reader tailing the file would
+	let block: BlockInfo = info.blocks[4];
+	let middle: &[u8] = &compressed[(block.offset + 10) as usize..];
+	let (_, recovered, report): (Cursor<&[u8]>, Vec<u8>, RecoveryReport) =
+		recover_with(Cursor::new(middle), Vec::new(), &info.config)?;
+	let start: usize = (block.original_offset + block.original_size) as usize;
+	assert_eq!(report.lost, vec![0..start as u64]);
+	assert_eq!(recovered, data[start..]);
+	Ok(())
+}
+
+#[test]
+fn test_compress_slice_parallel() -> AnyResult<()> {
+	let data: Vec<u8> = b"peter piper picked a peck of pickled peppers. ".repeat(2048);
+	for sync_markers in [false, true] {
+		let config: Config = Config {
+			block_size: Some(0x2000),
+			sync_markers,
+			..Config::default()
+		};
+		let parallel: Vec<u8> = compress_slice_parallel_with_threads(&data, &config, 4)?;
+		assert_eq!(
+			compress_slice_parallel_with_threads(&data, &config, 1)?,
+			parallel
+		);
+		assert_eq!(compress_slice_parallel(&data, &config)?, parallel);
+		// the same file as the sequential container makes
+		let sequential_config: Config = Config {
+			content_size: Some(data.len() as u64),
+			..config
+		};
+		let (_, sequential): (Cursor<&[u8]>, Vec<u8>) =
+			compress(Cursor::new(data.as_slice()), Vec::new(), &sequential_config)?;
+		assert_eq!(parallel, sequential);
+		assert!(is_srx(&parallel) && !is_srx(&data));
+		let (_, decompressed): (Cursor<&[u8]>, Vec<u8>) =
+			decompress(Cursor::new(parallel.as_slice()), Vec::new())?;
+		assert_eq!(decompressed, data);
+	}
+	assert!(compress_slice_parallel_with_threads(&data, &Config::default(), 0).is_err());
+	let empty: Vec<u8> = compress_slice_parallel(&[], &Config::default())?;
+	let (_, decompressed): (Cursor<&[u8]>, Vec<u8>) =
+		decompress(Cursor::new(empty.as_slice()), Vec::new())?;
+	assert!(decompressed.is_empty());
+	Ok(())
+}
+
+#[test]
+fn test_stream_writer() -> AnyResult<()> {
+	let data: Vec<u8> = b"she sells sea shells by the sea shore. ".repeat(512);
+	let config: Config = Config {
+		block_size: Some(0x1000),
+		..Config::default()
+	};
+
+	// written in pieces, it is the same file as the one compress makes
+	let mut writer: SrxStreamWriter<Vec<u8>> = SrxStreamWriter::new(Vec::new(), &config)?;
+	for piece in data.chunks(777) {
+		writer.write_all(piece)?;
+	}
+	let streamed: Vec<u8> = writer.finish()?;
+	let (_, compressed): (Cursor<&[u8]>, Vec<u8>) =
+		compress(Cursor::new(data.as_slice()), Vec::new(), &config)?;
+	assert_eq!(streamed, compressed);
+
+	// a flush ends the block, everything written before it can be decompressed right away
+	let mut writer: SrxStreamWriter<Vec<u8>> = SrxStreamWriter::new(Vec::new(), &config)?;
+	writer.write_all(&data[..100])?;
+	writer.flush()?;
+	writer.flush()?;
+	writer.write_all(&data[100..])?;
+	let streamed: Vec<u8> = writer.finish()?;
+	let info: FrameInfo = inspect(Cursor::new(streamed.as_slice()))?;
+	assert_eq!(info.blocks[0].original_size, 100);
+	assert_eq!(info.blocks[1].original_size, 0x1000);
+	assert_eq!(info.blocks.len(), 1 + (data.len() - 100).div_ceil(0x1000));
+	let (_, decompressed): (Cursor<&[u8]>, Vec<u8>) =
+		decompress(Cursor::new(streamed.as_slice()), Vec::new())?;
+	assert_eq!(decompressed, data);
+	Ok(())
+}
+
+#[test]
+fn test_pipe_tuning() -> AnyResult<()> {
+	let data: Vec<u8> = b"how much wood would a woodchuck chuck. ".repeat(0x4000);
+	let config: Config = Config::default();
+	let (_, expected, ()): (Cursor<&[u8]>, Vec<u8>, ()) = encode_tuned(
+		Cursor::new(data.as_slice()),
+		Vec::new(),
+		&config,
+		(),
+		&PipeTuning::DEFAULT,
+	)?;
+	// the pipes change the speed only, never the coded data, the tuning of the process is left
+	// alone as the other tests run at the same time
+	for (io_buffer_size, message_buffer_size, pipe_depth) in [
+		(0x40000, 0x10000, 4),
+		(0x100000, 0x40000, 2),
+		(0x400000, 0x10000, 8),
+	] {
+		let tuning: PipeTuning = PipeTuning {
+			io_buffer_size,
+			message_buffer_size,
+			pipe_depth,
+		};
+		let (_, compressed, ()): (Cursor<&[u8]>, Vec<u8>, ()) = encode_tuned(
+			Cursor::new(data.as_slice()),
+			Vec::new(),
+			&config,
+			(),
+			&tuning,
+		)?;
+		let (_, decompressed): (Cursor<&[u8]>, Vec<u8>) = decode_tuned(
+			Cursor::new(compressed.as_slice()),
+			Vec::new(),
+			&config,
+			&tuning,
+		)?;
+		let (_, length): (Cursor<&[u8]>, u64) =
+			verify_tuned(Cursor::new(compressed.as_slice()), &config, &tuning)?;
+		assert_eq!(compressed, expected);
+		assert_eq!(decompressed, data);
+		assert_eq!(length, data.len() as u64);
+	}
+	let invalid: PipeTuning = PipeTuning {
+		pipe_depth: 0,
+		..PipeTuning::DEFAULT
+	};
+	assert!(set_pipe_tuning(invalid).is_err());
+	Ok(())
+}
+
+#[cfg(feature = "tracing")]
+#[test]
+fn test_tracing_spans() -> AnyResult<()> {
+	use std::fmt::Debug;
+	use std::sync::{Arc, Mutex};
+	use tracing::field::{Field, Visit};
+	use tracing::span::{Attributes, Id, Record};
+	use tracing::{Event, Metadata, Subscriber};
+
+	// remembers the names of the spans and the messages of the events
+	struct Recorder(Arc<Mutex<Vec<String>>>);
+
+	struct Message(String);
+
+	impl Visit for Message {
+		fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+			if field.name() == "message" {
+				self.0 = format!("{:?}", value);
+			}
+		}
+	}
+
+	impl Subscriber for Recorder {
+		fn enabled(&self, _: &Metadata<'_>) -> bool {
+			true
+		}
+
+		fn new_span(&self, span: &Attributes<'_>) -> Id {
+			let mut names = self.0.lock().unwrap();
+			names.push(span.metadata().name().to_string());
+			Id::from_u64(names.len() as u64)
+		}
+
+		fn record(&self, _: &Id, _: &Record<'_>) {}
+
+		fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+		fn event(&self, event: &Event<'_>) {
+			let mut message: Message = Message(String::new());
+			event.record(&mut message);
+			self.0.lock().unwrap().push(message.0);
+		}
+
+		fn enter(&self, _: &Id) {}
+
+		fn exit(&self, _: &Id) {}
+	}
+
+	let data: Vec<u8> = b"a stitch in time saves nine. ".repeat(1024);
+	let config: Config = Config {
+		block_size: Some(0x2000),
+		..Config::default()
+	};
+	let names: Arc<Mutex<Vec<String>>> = Arc::new(Mutex::new(Vec::new()));
+	let decompressed: Vec<u8> =
+		tracing::subscriber::with_default(Recorder(names.clone()), || -> AnyResult<Vec<u8>> {
+			let (_, compressed): (Cursor<&[u8]>, Vec<u8>) =
+				compress(Cursor::new(data.as_slice()), Vec::new(), &config)?;
+			let (_, decompressed): (Cursor<&[u8]>, Vec<u8>) =
+				decompress(Cursor::new(compressed.as_slice()), Vec::new())?;
+			Ok(decompressed)
+		})?;
+	assert_eq!(decompressed, data);
+
+	// a span for each thread of each block, and the events of the pipe buffers, which are sent in
+	// the threads of the pipeline, reach the subscriber of this thread as well
+	let names: Vec<String> = names.lock().unwrap().clone();
+	let count = |name: &str| names.iter().filter(|found| *found == name).count();
+	let blocks: usize = data.len().div_ceil(0x2000);
+	for name in [
+		"encode",
+		"primary_context",
+		"secondary_context",
+		"decode",
+		"combined_context",
+	] {
+		assert_eq!(count(name), blocks);
+	}
+	assert_eq!(count("reader"), 2 * blocks);
+	assert_eq!(count("writer"), 2 * blocks);
+	assert_eq!(count("block compressed"), blocks);
+	assert_eq!(count("block decompressed"), blocks);
+	// three pipes in each encoder and two in each decoder, every buffer sent is received
+	assert!(count("pipe buffer sent") >= 5 * blocks);
+	assert_eq!(count("pipe buffer received"), count("pipe buffer sent"));
+	Ok(())
+}
+
+#[test]
+fn test_verify() -> AnyResult<()> {
+	let data: Vec<u8> = b"red lorry, yellow lorry. ".repeat(4096);
+	for block_size in [None, Some(0x4000)] {
+		let config: Config = Config {
+			checksum: Checksum::Crc32,
+			content_size: Some(data.len() as u64),
+			block_size,
+			..Config::default()
+		};
+		let (_, mut compressed): (Cursor<&[u8]>, Vec<u8>) =
+			compress(Cursor::new(data.as_slice()), Vec::new(), &config)?;
+		let report: VerifyReport = verify(Cursor::new(compressed.as_slice()))?;
+		assert_eq!(report.original_size, data.len() as u64);
+		assert_eq!(report.compressed_size, compressed.len() as u64);
+		assert_eq!(
+			report.blocks,
+			block_size.map_or(0, |block_size| data.len().div_ceil(block_size as usize)) as u64
+		);
+		// the blocks checked on many threads add up the same
+		for threads in [1, 3] {
+			assert_eq!(
+				verify_with_threads(Cursor::new(compressed.as_slice()), threads)?,
+				report
+			);
+		}
+
+		// cut short, then damaged in the middle
+		let length: usize = compressed.len();
+		assert!(verify(Cursor::new(&compressed[..length / 2])).is_err());
+		compressed[length / 2] ^= 0x10;
+		for threads in [1, 3] {
+			assert!(verify_with_threads(Cursor::new(compressed.as_slice()), threads).is_err());
+		}
+	}
+	Ok(())
+}
+
+#[test]
+fn test_match_stats() -> AnyResult<()> {
+	let data: Vec<u8> = b"she sells sea shells by the sea shore. ".repeat(256);
+	for block_size in [None, Some(0x1000)] {
+		let config: Config = Config {
+			block_size,
+			..Config::default()
+		};
+		// counting changes nothing in the file
+		let (_, plain): (Cursor<&[u8]>, Vec<u8>) =
+			compress(Cursor::new(data.as_slice()), Vec::new(), &config)?;
+		let (_, compressed, stats): (Cursor<&[u8]>, Vec<u8>, MatchStats) =
+			compress_with_stats(Cursor::new(data.as_slice()), Vec::new(), &config)?;
+		assert_eq!(compressed, plain);
+
+		// every byte is in a class, every literal in the histogram
+		assert_eq!(stats.total(), data.len() as u64);
+		assert_eq!(stats.literals.iter().sum::<u64>(), stats.no_match);
+		assert!(stats.match_first > stats.no_match);
+		assert!(stats.literal_entropy() > 0.0 && stats.literal_entropy() <= 8.0);
+		let top: Vec<(u8, u64)> = stats.top_literals(3);
+		assert!(top.windows(2).all(|pair| pair[0].1 >= pair[1].1));
+	}
+	Ok(())
+}
+
+#[test]
+fn test_low_memory_context_size() -> AnyResult<()> {
+	let data: Vec<u8> = b"how can a clam cram in a clean cream can? ".repeat(256);
+	assert_eq!(ContextSize::for_size(u64::MAX), ContextSize::DEFAULT);
+	assert_eq!(
+		Config::default().resolved_context_size(),
+		ContextSize::DEFAULT
+	);
+
+	// the small tables are recorded, the file reads back on any target
+	let config: Config = Config {
+		context_size: Some(ContextSize::LOW_MEMORY),
+		..Config::default()
+	};
+	let (_, compressed): (Cursor<&[u8]>, Vec<u8>) =
+		compress(Cursor::new(data.as_slice()), Vec::new(), &config)?;
+	let info: FrameInfo = inspect(Cursor::new(compressed.as_slice()))?;
+	assert_eq!(info.version, 1);
+	assert_eq!(info.config.context_size, Some(ContextSize::LOW_MEMORY));
+	let (_, decompressed): (Cursor<&[u8]>, Vec<u8>) =
+		decompress(Cursor::new(compressed.as_slice()), Vec::new())?;
+	assert_eq!(decompressed, data);
+	Ok(())
+}
+
+#[test]
+fn test_corrupted_block_sizes() -> AnyResult<()> {
+	let data: Vec<u8> = b"a big black bug bit a big black bear. ".repeat(1024);
+	let config: Config = Config {
+		block_size: Some(0x2000),
+		..Config::default()
+	};
+	let (_, compressed): (Cursor<&[u8]>, Vec<u8>) =
+		compress(Cursor::new(data.as_slice()), Vec::new(), &config)?;
+	let info: FrameInfo = inspect(Cursor::new(&compressed))?;
+	let patch = |at: usize, value: u64| -> Vec<u8> {
+		let mut patched: Vec<u8> = compressed.clone();
+		patched[at..at + 8].copy_from_slice(&value.to_le_bytes());
+		patched
+	};
+
+	// a block larger than the block size is refused before its memory is taken
+	let block_header: usize = info.blocks[1].offset as usize;
+	for size in [0x2001, 0x7fffffffffffff00] {
+		let patched: Vec<u8> = patch(block_header, size);
+		assert!(decompress(Cursor::new(patched.as_slice()), Vec::new()).is_err());
+		assert!(verify(Cursor::new(patched.as_slice())).is_err());
+	}
+
+	// a block decoding to more than its header says is cut off there
+	for size in [1, 0x1fff] {
+		let patched: Vec<u8> = patch(block_header, size);
+		assert!(decompress(Cursor::new(patched.as_slice()), Vec::new()).is_err());
+	}
+
+	// so is an index entry, and sizes adding up past the end of u64
+	let index: usize = compressed.len() - 16 - 24 * info.blocks.len();
+	for (entry, size) in [(0, 0x2001), (1, u64::MAX), (2, 0x7fffffffffffff00)] {
+		let patched: Vec<u8> = patch(index + 24 * entry + 8, size);
+		assert!(inspect(Cursor::new(&patched)).is_err());
+		assert!(SrxSeekableReader::new(Cursor::new(patched.as_slice())).is_err());
+	}
+	Ok(())
+}
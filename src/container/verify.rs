@@ -0,0 +1,81 @@
+/*
+ * srx: The fast Symbol Ranking based compressor.
+ * Copyright (C) 2023-2024  Mai Thanh Minh (a.k.a. thanhminhmr)
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU General Public License as published by the Free Software
+ * Foundation, either  version 3 of the  License,  or (at your option) any later
+ * version.
+ *
+ * This program  is distributed in the hope  that it will be useful, but WITHOUT
+ * ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+ * FOR  A PARTICULAR PURPOSE. See  the  GNU  General  Public   License  for more
+ * details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+ */
+
+use super::block::verify_blocks;
+use super::counter::Counter;
+use super::header::Header;
+use super::{check_content_size, verify_tuned};
+use crate::basic::{pipe_tuning, AnyResult};
+use crate::config::Config;
+use std::io::Read;
+use std::thread::available_parallelism;
+
+// -----------------------------------------------
+
+/// What [`verify`] checked of a srx file.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct VerifyReport {
+	/// Parameters recorded in the header, the checksum and the content size are what the data was
+	/// checked against beyond decoding to the end.
+	pub config: Config,
+	/// Size of the original data.
+	pub original_size: u64,
+	/// Size of the whole file in bytes, header included.
+	pub compressed_size: u64,
+	/// Number of blocks of a blocked file, zero otherwise.
+	pub blocks: u64,
+}
+
+// -----------------------------------------------
+
+/// Decode a srx file only to check it, against its checksum and its recorded size when it has
+/// them. The decoded data goes through the pipes of a decompression to a writer that only counts it,
+/// so nothing is written nor kept in memory. The blocks of a blocked file are checked on as many
+/// threads as there are cores (see [`verify_with_threads`]).
+pub fn verify<R: Read + Send>(reader: R) -> AnyResult<VerifyReport> {
+	let threads: usize = available_parallelism().map_or(1, |threads| threads.get());
+	verify_with_threads(reader, threads)
+}
+
+/// Decode a srx file only to check it like [`verify`] does, checking the blocks of a blocked file on
+/// up to the given number of threads at the same time. Decoding is most of the work of
+/// decompressing, this is where checking gets faster than that: each thread runs a whole codec,
+/// with the memory told by [`memory_usage`] for a block, so the count should fit the memory as well
+/// as the cores. A file that is not blocked is decoded on one thread.
+///
+/// [`memory_usage`]: crate::memory_usage
+pub fn verify_with_threads<R: Read + Send>(reader: R, threads: usize) -> AnyResult<VerifyReport> {
+	let mut reader: Counter<R> = Counter::new(reader);
+	let header: Header = Header::read(&mut reader)?;
+	let (reader, original_size, blocks): (Counter<R>, u64, u64) = match header.config.block_size {
+		None => {
+			let (reader, original_size): (Counter<R>, u64) =
+				verify_tuned(reader, &header.config, &pipe_tuning())?;
+			(reader, original_size, 0)
+		}
+		Some(block_size) => verify_blocks(reader, &header.config, block_size, threads)?,
+	};
+	check_content_size(&header.config, original_size)?;
+	Ok(VerifyReport {
+		config: header.config,
+		original_size,
+		compressed_size: reader.count(),
+		blocks,
+	})
+}
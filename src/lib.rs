@@ -0,0 +1,41 @@
+/*
+ * srx: The fast Symbol Ranking based compressor.
+ * Copyright (C) 2023-2024  Mai Thanh Minh (a.k.a. thanhminhmr)
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU General Public License as published by the Free Software
+ * Foundation, either  version 3 of the  License,  or (at your option) any later
+ * version.
+ *
+ * This program  is distributed in the hope  that it will be useful, but WITHOUT
+ * ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+ * FOR  A PARTICULAR PURPOSE. See  the  GNU  General  Public   License  for more
+ * details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+ */
+
+pub use crate::basic::{
+	buffer_pool_size, pipe_tuning, set_buffer_pool_limit, set_pipe_tuning, AnyError, AnyResult,
+	Bit, Byte, PipeTuning,
+};
+pub use crate::checksum::Checksum;
+pub use crate::codec::{decode, encode, MatchStats};
+#[cfg(feature = "trace")]
+pub use crate::codec::{encode_traced, TraceEvent, TraceLog, TraceSink};
+pub use crate::config::{Config, ContextSize};
+pub use crate::container::{
+	compress, compress_slice_parallel, compress_slice_parallel_with_threads, compress_with_stats,
+	decompress, inspect, is_srx, memory_usage, recover, recover_with, verify, verify_with_threads,
+	BlockInfo, FrameInfo, RecoveryReport, SrxSeekableReader, SrxStreamWriter, VerifyReport,
+};
+
+mod basic;
+mod checksum;
+mod codec;
+mod config;
+mod container;
+pub mod primary_context;
+pub mod secondary_context;
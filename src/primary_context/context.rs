@@ -24,25 +24,37 @@ use super::matched::ByteMatched;
 
 // -----------------------------------------------
 
-// The symbol ranking context that saves last 3 values of next byte
-pub struct PrimaryContext<const SIZE: usize> {
+/// The symbol ranking context that saves last 3 values of next byte.
+///
+/// The context is a hash table of [`ByteHistory`] indexed by a rolling hash of the previous
+/// bytes. The encoder calls [`matching`](Self::matching) with each input byte, the decoder calls
+/// [`matched`](Self::matched) with each decoded byte, both keep the table in the same state.
+pub struct PrimaryContext {
 	previous_byte: Byte,
 	hash_value: usize,
-	context: Buffer<ByteHistory, SIZE>,
+	context: Buffer<ByteHistory>,
 }
 
-impl<const SIZE: usize> PrimaryContext<SIZE> {
-	// assert that SIZE is power of 2
-	const _SIZE_CHECK: () = assert!(SIZE != 0 && (SIZE & (SIZE - 1)) == 0);
-
-	pub fn new() -> Self {
+impl PrimaryContext {
+	/// Create a context with `size` entries (4 bytes each), `size` must be a power of 2.
+	pub fn new(size: usize) -> Self {
+		assert!(
+			size != 0 && (size & (size - 1)) == 0,
+			"Context size must be a power of 2!"
+		);
 		Self {
 			previous_byte: Byte::from(0),
 			hash_value: 0,
-			context: Buffer::new(),
+			context: Buffer::new(size),
 		}
 	}
 
+	/// Number of entries in the context.
+	pub fn size(&self) -> usize {
+		self.context.len()
+	}
+
+	/// Get the ranked candidates for the next byte.
 	pub fn get_info(&self) -> PrimaryContextInfo {
 		let current_history: ByteHistory = self.context[self.hash_value];
 		PrimaryContextInfo {
@@ -55,28 +67,36 @@ impl<const SIZE: usize> PrimaryContext<SIZE> {
 		}
 	}
 
-	fn next_hash(hash_value: usize, next_byte: Byte) -> usize {
-		(hash_value * (5 << 5) + usize::from(next_byte) + 1) % SIZE
+	fn next_hash(&self, next_byte: Byte) -> usize {
+		// only the low bits are kept, they are the same whether the usize wraps at 32 or 64 bits
+		self.hash_value
+			.wrapping_mul(5 << 5)
+			.wrapping_add(usize::from(next_byte) + 1)
+			& (self.context.len() - 1)
 	}
 
+	/// Rank the next byte against the candidates and update the context with it.
 	pub fn matching(&mut self, next_byte: Byte) -> ByteMatched {
 		let matched: ByteMatched = self.context[self.hash_value].matching(next_byte);
 		self.previous_byte = next_byte;
-		self.hash_value = Self::next_hash(self.hash_value, next_byte);
-		debug_assert!(self.hash_value < SIZE);
+		self.hash_value = self.next_hash(next_byte);
+		debug_assert!(self.hash_value < self.context.len());
 		matched
 	}
 
+	/// Update the context with the next byte whose rank is already known.
 	pub fn matched(&mut self, next_byte: Byte, matched: ByteMatched) {
 		self.context[self.hash_value].matched(next_byte, matched);
 		self.previous_byte = next_byte;
-		self.hash_value = Self::next_hash(self.hash_value, next_byte);
-		debug_assert!(self.hash_value < SIZE);
+		self.hash_value = self.next_hash(next_byte);
+		debug_assert!(self.hash_value < self.context.len());
 	}
 }
 
 // -----------------------------------------------
 
+/// A snapshot of the primary context before coding the next byte.
+#[derive(Copy, Clone, Debug)]
 pub struct PrimaryContextInfo {
 	previous_byte: Byte,
 	first_byte: Byte,
@@ -87,26 +107,32 @@ pub struct PrimaryContextInfo {
 }
 
 impl PrimaryContextInfo {
+	/// The last coded byte.
 	pub fn previous_byte(&self) -> Byte {
 		self.previous_byte
 	}
 
+	/// The index of the current entry in the context.
 	pub fn hash_value(&self) -> usize {
 		self.hash_value
 	}
 
+	/// The most likely next byte.
 	pub fn first_byte(&self) -> Byte {
 		self.first_byte
 	}
 
+	/// The second most likely next byte.
 	pub fn second_byte(&self) -> Byte {
 		self.second_byte
 	}
 
+	/// The third most likely next byte.
 	pub fn third_byte(&self) -> Byte {
 		self.third_byte
 	}
 
+	/// How many times in a row the first byte has been matched (saturated).
 	pub fn match_count(&self) -> usize {
 		self.match_count
 	}
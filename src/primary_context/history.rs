@@ -62,18 +62,22 @@ impl HistoryState {
 
 // -----------------------------------------------
 
-#[derive(Clone, Copy, Default)]
-pub(crate) struct ByteHistory(u32);
+/// The last 3 distinct bytes seen in a context, most recent first, and how well they predict.
+#[derive(Clone, Copy, Default, Debug)]
+pub struct ByteHistory(u32);
 
 impl ByteHistory {
+	/// The most likely next byte.
 	pub fn first_byte(self) -> Byte {
 		Byte::from((self.0 >> 8) & 0xFF)
 	}
 
+	/// The second most likely next byte.
 	pub fn second_byte(self) -> Byte {
 		Byte::from((self.0 >> 16) & 0xFF)
 	}
 
+	/// The third most likely next byte.
 	pub fn third_byte(self) -> Byte {
 		Byte::from(self.0 >> 24)
 	}
@@ -82,10 +86,12 @@ impl ByteHistory {
 		STATE_TABLE[(self.0 & 0xFF) as usize]
 	}
 
+	/// How many times in a row the first byte has been matched (saturated).
 	pub fn match_count(self) -> usize {
 		self.state().match_count()
 	}
 
+	/// Rank the next byte against the history, then move it to the front.
 	pub fn matching(&mut self, next_byte: Byte) -> ByteMatched {
 		let byte_history: u32 = self.0;
 		let mask: u32 = byte_history ^ (0x01_01_01_00 * u32::from(next_byte));
@@ -119,6 +125,7 @@ impl ByteHistory {
 		matched
 	}
 
+	/// Move the next byte to the front, its rank is already known.
 	pub fn matched(&mut self, next_byte: Byte, matched: ByteMatched) {
 		let byte_history: u32 = self.0;
 		let updated_history: u32 = match matched {
@@ -17,10 +17,15 @@
  *
  */
 
+/// The rank of a byte in its [`ByteHistory`](super::ByteHistory).
 #[derive(Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
 pub enum ByteMatched {
+	/// The byte is the first candidate.
 	MatchFirst,
+	/// The byte is none of the candidates.
 	NoMatch,
+	/// The byte is the second candidate.
 	MatchSecond,
+	/// The byte is the third candidate.
 	MatchThird,
 }
@@ -17,7 +17,13 @@
  *
  */
 
+//! The primary context: a symbol ranking model.
+//!
+//! For each context (a hash of the previous bytes) the model keeps the last 3 distinct bytes that
+//! followed it, ranked by recency. Each byte is then coded by its rank, see [`ByteMatched`].
+
 pub use self::context::{PrimaryContext, PrimaryContextInfo};
+pub use self::history::ByteHistory;
 pub use self::matched::ByteMatched;
 
 mod context;
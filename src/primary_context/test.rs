@@ -86,6 +86,7 @@ fn increase(mut value: u8, max: u8) -> u8 {
 	}
 }
 
+#[allow(dead_code)]
 fn decrease(mut value: u8, max: u8) -> u8 {
 	value = value.saturating_sub(1);
 	if value >= max {
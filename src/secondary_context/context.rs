@@ -17,29 +17,52 @@
  *
  */
 
-use super::state::{BitState, StateInfo};
+use super::state::{AdaptationProfile, BitState, StateInfo, StateTable};
 use crate::basic::Bit;
 use crate::basic::Buffer;
 
-pub struct SecondaryContext<const SIZE: usize> {
-	context: Buffer<BitState, SIZE>,
+/// A table of [`BitState`], one adaptive bit predictor per context index.
+pub struct SecondaryContext {
+	profile: AdaptationProfile,
+	state_table: &'static StateTable,
+	context: Buffer<BitState>,
 }
 
-impl<const SIZE: usize> SecondaryContext<SIZE> {
-	pub fn new() -> Self {
+impl SecondaryContext {
+	/// Create a context with `size` entries (2 bytes each), using the normal profile.
+	pub fn new(size: usize) -> Self {
+		Self::with_profile(size, AdaptationProfile::Normal)
+	}
+
+	/// Create a context with `size` entries (2 bytes each), using the given profile.
+	pub fn with_profile(size: usize, profile: AdaptationProfile) -> Self {
 		Self {
-			context: Buffer::new(),
+			profile,
+			state_table: profile.state_table(),
+			context: Buffer::new(size),
 		}
 	}
 
+	/// The adaptation profile of the context.
+	pub fn profile(&self) -> AdaptationProfile {
+		self.profile
+	}
+
+	/// Number of entries in the context.
+	pub fn size(&self) -> usize {
+		self.context.len()
+	}
+
+	/// Get the current state, and so the prediction, at the context index.
 	pub fn get_info(&self, context_index: usize) -> StateInfo {
-		debug_assert!(context_index < SIZE);
-		self.context[context_index].get_info()
+		debug_assert!(context_index < self.context.len());
+		self.context[context_index].get_info_from(self.state_table)
 	}
 
-	// return current prediction and then update the prediction with new bit
+	/// Update the state at the context index with the coded bit.
 	pub fn update(&mut self, current_state: StateInfo, context_index: usize, bit: Bit) {
-		debug_assert!(context_index < SIZE);
+		debug_assert!(context_index < self.context.len());
+		debug_assert!(self.get_info(context_index) == current_state);
 		self.context[context_index].update(current_state, bit)
 	}
 }
@@ -18,7 +18,14 @@
  */
 
 use crate::basic::Bit;
-use crate::basic::{AnyResult, BufferedInputPipe, Closable};
+use crate::basic::{AnyError, AnyResult, BufferedInputPipe, Closable};
+
+// -----------------------------------------------
+
+// the decoder runs 4 bytes ahead of the encoder, which writes one last byte when closed, so a valid
+// stream never needs more than 3 bytes past its end: this holds for the streams of this encoder only,
+// one that writes less when closed (as other implementations of srx may) would be taken as truncated
+const MAX_PADDING: u32 = 3;
 
 // -----------------------------------------------
 
@@ -26,6 +33,7 @@ pub struct BitDecoder<const SIZE: usize> {
 	value: u32,
 	low: u32,
 	high: u32,
+	padding: u32,
 	input: BufferedInputPipe<u8, SIZE>,
 }
 
@@ -35,6 +43,7 @@ impl<const SIZE: usize> BitDecoder<SIZE> {
 			value: 0,
 			low: 0,
 			high: 0,
+			padding: 0,
 			input,
 		}
 	}
@@ -44,8 +53,16 @@ impl<const SIZE: usize> BitDecoder<SIZE> {
 	fn flush(&mut self) -> AnyResult<()> {
 		debug_assert!((self.high ^ self.low) < 0x01000000);
 		while {
-			// shift byte in
-			self.value = (self.value << 8) | self.input.produce()?.unwrap_or(0xFF) as u32;
+			// shift byte in, a stream running too far past its end is truncated or corrupted
+			let byte: u8 = match self.input.produce()? {
+				Some(byte) => byte,
+				None if self.padding < MAX_PADDING => {
+					self.padding += 1;
+					0xFF
+				}
+				None => return Err(AnyError::from_string("Unexpected end of SRX stream!")),
+			};
+			self.value = (self.value << 8) | byte as u32;
 			// shift new bits into high/low
 			self.low <<= 8;
 			self.high = (self.high << 8) | 0xFF;
@@ -17,12 +17,18 @@
  *
  */
 
+//! The secondary context: adaptive bit prediction.
+//!
+//! The ranks and literals produced by the primary context are coded bit by bit, each bit at a
+//! context index chosen by the caller. Every index holds a [`BitState`] that predicts the next bit
+//! and adapts to the bits it has seen.
+
 mod context;
 mod decoder;
 mod encoder;
 mod state;
 
 pub use self::context::SecondaryContext;
-pub use self::decoder::BitDecoder;
-pub use self::encoder::BitEncoder;
-pub use self::state::StateInfo;
+pub(crate) use self::decoder::BitDecoder;
+pub(crate) use self::encoder::BitEncoder;
+pub use self::state::{AdaptationProfile, BitState, StateInfo};
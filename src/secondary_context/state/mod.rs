@@ -17,9 +17,13 @@
  *
  */
 
+mod profile;
+#[allow(clippy::module_inception)]
 mod state;
 #[cfg(test)]
 mod test;
 
+pub use self::profile::AdaptationProfile;
 pub use self::state::BitState;
 pub use self::state::StateInfo;
+pub(crate) use self::state::StateTable;
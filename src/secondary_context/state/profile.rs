@@ -0,0 +1,81 @@
+/*
+ * srx: The fast Symbol Ranking based compressor.
+ * Copyright (C) 2023-2024  Mai Thanh Minh (a.k.a. thanhminhmr)
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU General Public License as published by the Free Software
+ * Foundation, either  version 3 of the  License,  or (at your option) any later
+ * version.
+ *
+ * This program  is distributed in the hope  that it will be useful, but WITHOUT
+ * ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+ * FOR  A PARTICULAR PURPOSE. See  the  GNU  General  Public   License  for more
+ * details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+ */
+
+use super::state::{
+	StateTable, FAST_STATE_TABLE, SLOW_STATE_TABLE, STATE_TABLE, STATIONARY_STATE_TABLE,
+};
+
+// -----------------------------------------------
+
+/// How fast the bit predictors follow changes in the data, each profile has its own state table.
+///
+/// A predictor counts the bits it has seen up to a limit, then keeps adapting at a fixed rate of
+/// about one over the limit. A low limit forgets old statistics quickly, a high limit averages over
+/// a longer history.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Default)]
+pub enum AdaptationProfile {
+	/// The original srx table (limit 128), a good default for most data.
+	#[default]
+	Normal,
+	/// Limit 32, for short or bursty data whose statistics change often.
+	Fast,
+	/// Limit 255, for long homogeneous data.
+	Slow,
+	/// Limit 352, close to plain counting, for data with stationary statistics.
+	Stationary,
+}
+
+impl AdaptationProfile {
+	/// All profiles, in the order of their identifiers.
+	pub const ALL: [AdaptationProfile; 4] =
+		[Self::Normal, Self::Fast, Self::Slow, Self::Stationary];
+
+	/// The name used on the command line.
+	pub fn name(self) -> &'static str {
+		match self {
+			Self::Normal => "normal",
+			Self::Fast => "fast",
+			Self::Slow => "slow",
+			Self::Stationary => "stationary",
+		}
+	}
+
+	/// Find a profile by its name.
+	pub fn from_name(name: &str) -> Option<Self> {
+		Self::ALL.into_iter().find(|profile| profile.name() == name)
+	}
+
+	// the identifier stored in the file header
+	pub(crate) fn id(self) -> u8 {
+		self as u8
+	}
+
+	pub(crate) fn from_id(id: u8) -> Option<Self> {
+		Self::ALL.get(id as usize).copied()
+	}
+
+	pub(crate) fn state_table(self) -> &'static StateTable {
+		match self {
+			Self::Normal => STATE_TABLE,
+			Self::Fast => FAST_STATE_TABLE,
+			Self::Slow => SLOW_STATE_TABLE,
+			Self::Stationary => STATIONARY_STATE_TABLE,
+		}
+	}
+}
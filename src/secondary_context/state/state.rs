@@ -16,27 +16,36 @@
  * this program. If not, see <https://www.gnu.org/licenses/>.
  *
  */
+use super::profile::AdaptationProfile;
 use crate::basic::Bit;
 
 // -----------------------------------------------
 
-// Split the giant table to another non-rs file to keep IDE happy
-pub const STATE_TABLE: &[StateInfo] = include!("state_table.inc");
+pub type StateTable = [StateInfo; 0x10000];
+
+// Split the giant tables to other non-rs files to keep IDE happy
+pub const STATE_TABLE: &StateTable = include!("state_table.inc");
+pub const FAST_STATE_TABLE: &StateTable = include!("state_table_fast.inc");
+pub const SLOW_STATE_TABLE: &StateTable = include!("state_table_slow.inc");
+pub const STATIONARY_STATE_TABLE: &StateTable = include!("state_table_stationary.inc");
 
 // -----------------------------------------------
 
+/// A state of the bit predictor: its prediction and the next state for each bit.
 #[derive(Copy, Clone, Eq, PartialEq, Debug)]
 pub struct StateInfo(u64);
 
 impl StateInfo {
-	pub const fn new(prediction: u32, next_if_zero: u16, next_if_one: u16) -> Self {
+	pub(crate) const fn new(prediction: u32, next_if_zero: u16, next_if_one: u16) -> Self {
 		Self(((prediction as u64) << 32) | ((next_if_zero as u64) << 16) | (next_if_one as u64))
 	}
 
+	/// Index of the next state after coding the bit.
 	pub fn next(&self, bit: Bit) -> u16 {
 		(if bit.into() { self.0 } else { self.0 >> 16 }) as u16
 	}
 
+	/// Probability of a one bit, scaled to 2^32.
 	pub fn prediction(&self) -> u32 {
 		(self.0 >> 32) as u32
 	}
@@ -44,16 +53,31 @@ impl StateInfo {
 
 // -----------------------------------------------
 
-#[derive(Copy, Clone, Default)]
+/// An adaptive bit predictor, stored as an index into the state table of its profile.
+#[derive(Copy, Clone, Default, Debug)]
 pub struct BitState(u16);
 
 impl BitState {
+	/// Get the current state, in the table of the normal profile.
 	pub fn get_info(&self) -> StateInfo {
-		STATE_TABLE[self.0 as usize]
+		self.get_info_from(STATE_TABLE)
+	}
+
+	/// Get the current state, in the table of the given profile.
+	pub fn get_info_with(&self, profile: AdaptationProfile) -> StateInfo {
+		self.get_info_from(profile.state_table())
+	}
+
+	pub(crate) fn get_info_from(&self, state_table: &StateTable) -> StateInfo {
+		state_table[self.0 as usize]
 	}
 
+	/// Move to the next state, `current_state` must be the one returned by `get_info` (or
+	/// `get_info_with` when using another profile).
 	pub fn update(&mut self, current_state: StateInfo, bit: Bit) {
-		debug_assert!(STATE_TABLE[self.0 as usize] == current_state);
+		debug_assert!(AdaptationProfile::ALL
+			.iter()
+			.any(|profile| self.get_info_with(*profile) == current_state));
 		self.0 = current_state.next(bit);
 	}
 }
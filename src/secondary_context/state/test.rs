@@ -30,6 +30,7 @@ use std::path::Path;
 use crate::basic::AnyResult;
 use crate::basic::Bit;
 
+use super::profile::AdaptationProfile;
 use super::state::StateInfo;
 
 // -----------------------------------------------
@@ -318,7 +319,7 @@ fn prediction_rescaling(numerator: u64, denominator: u64) -> f64 {
 }
 
 fn prediction_next(
-	predictions: &Vec<f64>,
+	predictions: &[f64],
 	current_state: StateIndex,
 	next_count: u64,
 	bit: Bit,
@@ -413,7 +414,7 @@ impl PrimitiveStateTable {
 		);
 	}
 
-	fn state_manual(&mut self, prediction: &Vec<f64>, current_state: StateIndex, next_count: u64) {
+	fn state_manual(&mut self, prediction: &[f64], current_state: StateIndex, next_count: u64) {
 		self.state(
 			current_state,
 			prediction_next(prediction, current_state, next_count, Bit::Zero),
@@ -561,21 +562,8 @@ fn table(size: u64, limit_level: u64) -> AnyResult<PrimitiveStateTable> {
 
 // -----------------------------------------------
 
-#[test]
-fn test_and_generate_state_table() -> AnyResult<()> {
-	const SIZE: u64 = 16;
-
-	// create table
-	let table = table(SIZE, 128)?;
-
-	// dbg!(&table);
-
-	// export to Gephi (*.gexf)
-	table.export()?;
-
-	// check for valid table
-	assert_eq!(table.map.len(), 1 << SIZE);
-
+// print the table in the format of state_table*.inc
+fn state_table(table: &PrimitiveStateTable) -> Vec<StateInfo> {
 	// get the states as an array
 	let mut data: Vec<&PrimitiveState> = table.map.values().collect();
 	data.sort_by_key(|x| x.current_state);
@@ -587,13 +575,9 @@ fn test_and_generate_state_table() -> AnyResult<()> {
 	});
 
 	// create next states array
-	println!(
-		"// length = {}\npub const STATE_TABLE: &[StateInfo] = &[",
-		data.len()
-	);
+	println!("// length = {}\n&[", data.len());
 	let mut state_table: Vec<StateInfo> = Vec::new();
-	for index in 0..1 << SIZE {
-		let state: &PrimitiveState = data[index];
+	for (index, &state) in data.iter().enumerate() {
 		let level: usize = state.current_state.count as usize;
 		let prediction: u32 = u32::from(state.current_state.value);
 		let next_if_zero: u16 = *data_index.get(&state.next_if_zero).unwrap() as u16;
@@ -604,9 +588,39 @@ fn test_and_generate_state_table() -> AnyResult<()> {
 			prediction, next_if_zero, next_if_one, index, level,
 		);
 	}
-	println!("];");
+	println!("]");
+	state_table
+}
+
+// -----------------------------------------------
 
-	// debug_assert!(state_table.eq(STATE_TABLE));
+#[test]
+fn test_and_generate_state_table() -> AnyResult<()> {
+	const SIZE: u64 = 16;
+
+	for (profile, limit_level) in [
+		(AdaptationProfile::Normal, 128),
+		(AdaptationProfile::Fast, 32),
+		(AdaptationProfile::Slow, 255),
+		(AdaptationProfile::Stationary, 352),
+	] {
+		// create table
+		let table = table(SIZE, limit_level)?;
+
+		// dbg!(&table);
+
+		// export to Gephi (*.gexf)
+		if profile == AdaptationProfile::Normal {
+			table.export()?;
+		}
+
+		// check for valid table
+		assert_eq!(table.map.len(), 1 << SIZE);
+
+		// check that the generated table is the one in use
+		println!("// profile = {}", profile.name());
+		assert!(state_table(&table).eq(profile.state_table()));
+	}
 
 	Ok(())
 }
@@ -0,0 +1,57 @@
+/*
+ * srx: The fast Symbol Ranking based compressor.
+ * Copyright (C) 2023-2024  Mai Thanh Minh (a.k.a. thanhminhmr)
+ *
+ * This program is free software: you can redistribute it and/or modify it under
+ * the terms of the GNU General Public License as published by the Free Software
+ * Foundation, either  version 3 of the  License,  or (at your option) any later
+ * version.
+ *
+ * This program  is distributed in the hope  that it will be useful, but WITHOUT
+ * ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+ * FOR  A PARTICULAR PURPOSE. See  the  GNU  General  Public   License  for more
+ * details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+ */
+
+// The buffer pool is shared by the whole process, so it is tested in a binary of its own where no
+// other test runs the codec at the same time.
+
+use srx::{buffer_pool_size, compress, decompress, set_buffer_pool_limit, AnyResult, Config};
+use std::io::Cursor;
+
+// -----------------------------------------------
+
+#[test]
+fn test_codec_reuses_the_pooled_buffers() -> AnyResult<()> {
+	let data: Vec<u8> = (0..0x30000_usize)
+		.map(|index| ((index * index) >> 7) as u8)
+		.collect();
+	let round_trip = || -> AnyResult<Vec<u8>> {
+		let (_, compressed): (Cursor<&[u8]>, Vec<u8>) =
+			compress(Cursor::new(data.as_slice()), Vec::new(), &Config::default())?;
+		let (_, decompressed): (Cursor<Vec<u8>>, Vec<u8>) =
+			decompress(Cursor::new(compressed), Vec::new())?;
+		Ok(decompressed)
+	};
+
+	// disabled by default: nothing is kept
+	assert_eq!(round_trip()?, data);
+	assert_eq!(buffer_pool_size(), 0);
+
+	// the buffers and context tables of the first pass are kept, the second pass takes them again
+	// instead of allocating more, so the pool does not grow
+	set_buffer_pool_limit(usize::MAX);
+	assert_eq!(round_trip()?, data);
+	let kept: usize = buffer_pool_size();
+	assert!(kept > 0);
+	assert_eq!(round_trip()?, data);
+	assert_eq!(buffer_pool_size(), kept);
+
+	set_buffer_pool_limit(0);
+	assert_eq!(buffer_pool_size(), 0);
+	Ok(())
+}